@@ -0,0 +1,206 @@
+use crate::canvas::surface::Surface;
+use crate::color::Color;
+use crate::point::Point;
+use crate::rectangle::Rectangle;
+use crate::segment::Segment;
+
+pub const MIN_SIZE: u32 = 1;
+pub const MAX_SIZE: u32 = 100;
+pub const MAX_REACH: i32 = MAX_SIZE as i32 / 2 + 1;
+
+const CALLIGRAPHY: [&str; 8] = [
+    "......##", ".....###", "....###.", "...###..", "..###...", ".###....", "###.....", "##......",
+];
+
+const SPRAY: [&str; 8] = [
+    "..#....#", "#....#..", "...#...#", ".#..#...", "#.....#.", "..#.#...", ".#....#.", "#..#...#",
+];
+
+#[derive(Clone)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub mask: Vec<bool>,
+}
+
+impl Bitmap {
+    pub fn new(width: u32, height: u32, mask: Vec<bool>) -> Bitmap {
+        Bitmap {
+            width,
+            height,
+            mask,
+        }
+    }
+
+    pub fn calligraphy() -> Bitmap {
+        Bitmap::parse(&CALLIGRAPHY)
+    }
+
+    pub fn spray() -> Bitmap {
+        Bitmap::parse(&SPRAY)
+    }
+
+    fn parse(rows: &[&str]) -> Bitmap {
+        let width = rows[0].len() as u32;
+        let height = rows.len() as u32;
+        let mask = rows
+            .iter()
+            .flat_map(|row| row.chars().map(|char| char == '#'))
+            .collect();
+        Bitmap::new(width, height, mask)
+    }
+
+    fn is_set(&self, x: u32, y: u32) -> bool {
+        self.mask[(y * self.width + x) as usize]
+    }
+}
+
+#[derive(Clone)]
+pub enum Tip {
+    Round,
+    Square,
+    Bitmap(Bitmap),
+}
+
+#[derive(Clone)]
+pub struct Brush {
+    pub size: u32,
+    pub tip: Tip,
+}
+
+impl Brush {
+    pub fn new(size: u32, tip: Tip) -> Brush {
+        Brush {
+            size: size.clamp(MIN_SIZE, MAX_SIZE),
+            tip,
+        }
+    }
+
+    /// How far the brush reaches from the point it is stamped at.
+    pub fn reach(&self) -> i32 {
+        self.size as i32 / 2 + 1
+    }
+
+    /// Offsets, relative to the stamped point, of pixels covered by a single stamp.
+    pub fn stamp(&self) -> Vec<Point> {
+        let size = self.size as i32;
+        let offset = (size - 1) / 2;
+        let center = (size - 1) as f64 / 2_f64;
+        let radius = size as f64 / 2_f64;
+        let mut stamp = vec![];
+        match &self.tip {
+            Tip::Round => {
+                for y in 0..size {
+                    for x in 0..size {
+                        let dx = x as f64 - center;
+                        let dy = y as f64 - center;
+                        if dx * dx + dy * dy <= radius * radius {
+                            stamp.push(Point::new(x - offset, y - offset));
+                        }
+                    }
+                }
+            }
+            Tip::Square => {
+                for y in 0..size {
+                    for x in 0..size {
+                        stamp.push(Point::new(x - offset, y - offset));
+                    }
+                }
+            }
+            Tip::Bitmap(bitmap) => {
+                let scale = self.size as f64 / bitmap.width.max(bitmap.height) as f64;
+                let width = ((bitmap.width as f64 * scale).round() as i32).max(1);
+                let height = ((bitmap.height as f64 * scale).round() as i32).max(1);
+                for y in 0..height {
+                    for x in 0..width {
+                        let src_x = ((x as f64 / scale) as u32).min(bitmap.width - 1);
+                        let src_y = ((y as f64 / scale) as u32).min(bitmap.height - 1);
+                        if bitmap.is_set(src_x, src_y) {
+                            stamp.push(Point::new(x - (width - 1) / 2, y - (height - 1) / 2));
+                        }
+                    }
+                }
+            }
+        }
+        stamp
+    }
+
+    /// Stamps the brush on every point of the segment, skipping pixels outside of the canvas, and
+    /// composites the color over the covered pixels. Returns the area that may have changed.
+    pub fn put(&self, pixels: &mut Surface, segment: &Segment, color: &Color) -> Option<Rectangle> {
+        self.put_points(pixels, &segment.points(), color)
+    }
+
+    /// Stamps the brush on every given point, skipping pixels outside of the canvas, and
+    /// composites the color over the covered pixels. Returns the area that may have changed.
+    pub fn put_points(
+        &self,
+        pixels: &mut Surface,
+        points: &[Point],
+        color: &Color,
+    ) -> Option<Rectangle> {
+        self.apply(pixels, points, |pixels, point| pixels.blend(point, color))
+    }
+
+    /// Like `put`, but composites over `base` rather than over the pixels themselves, so that the
+    /// segments of a stroke painted over the same `base` do not build up where they overlap.
+    pub fn put_over(
+        &self,
+        pixels: &mut Surface,
+        base: &Surface,
+        segment: &Segment,
+        color: &Color,
+    ) -> Option<Rectangle> {
+        self.apply(pixels, &segment.points(), |pixels, point| {
+            pixels.set(point, &color.over(&base.get(point), 255))
+        })
+    }
+
+    /// Like `put`, but replaces the covered pixels with the color, whatever its alpha.
+    pub fn replace(
+        &self,
+        pixels: &mut Surface,
+        segment: &Segment,
+        color: &Color,
+    ) -> Option<Rectangle> {
+        self.apply(pixels, &segment.points(), |pixels, point| {
+            pixels.set(point, color)
+        })
+    }
+
+    /// Calls `paint` once for every pixel of the canvas covered by stamps on the points, however
+    /// many stamps cover it. Returns the area that may have changed.
+    fn apply(
+        &self,
+        pixels: &mut Surface,
+        points: &[Point],
+        mut paint: impl FnMut(&mut Surface, &Point),
+    ) -> Option<Rectangle> {
+        let stamp = self.stamp();
+        let from = Point::new(
+            points.iter().map(|point| point.x).min()? + stamp.iter().map(|delta| delta.x).min()?,
+            points.iter().map(|point| point.y).min()? + stamp.iter().map(|delta| delta.y).min()?,
+        );
+        let to = Point::new(
+            points.iter().map(|point| point.x).max()? + stamp.iter().map(|delta| delta.x).max()?,
+            points.iter().map(|point| point.y).max()? + stamp.iter().map(|delta| delta.y).max()?,
+        );
+        let bounds = Rectangle::new(from, to).clip(&pixels.width(), &pixels.height())?;
+        let mut is_painted = vec![false; (bounds.width() * bounds.height()) as usize];
+        for point in points {
+            for delta in &stamp {
+                let target = Point::new(point.x + delta.x, point.y + delta.y);
+                if !bounds.contains(&target) {
+                    continue;
+                }
+                let idx = ((target.y - bounds.from.y) * bounds.width() as i32 + target.x
+                    - bounds.from.x) as usize;
+                if !is_painted[idx] {
+                    is_painted[idx] = true;
+                    paint(pixels, &target);
+                }
+            }
+        }
+        Some(bounds)
+    }
+}
@@ -1,24 +1,41 @@
-use crate::canvas::canvas_events::CanvasEvents;
+use crate::canvas::canvas_events::{self, CanvasEvents};
+use crate::canvas::layer::Layer;
+use crate::canvas::overlay::{self, Overlay};
+use crate::canvas::selection::{Floating, Selection};
+use crate::canvas::surface::Surface;
+use crate::canvas::viewport::{self, Viewport};
 use crate::color::Color;
 use crate::dom::Dom;
+use crate::layer_panel;
 use crate::point::Point;
+use crate::rectangle::Rectangle;
 use crate::resizer;
 use crate::segment::Segment;
-use crate::util::flat_idx;
 use std::cell::RefCell;
 use std::rc::Rc;
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen::{Clamped, JsCast};
 use web_sys::{
-    CanvasRenderingContext2d, Document, HtmlCanvasElement, HtmlElement, ImageData, MouseEvent,
+    CanvasRenderingContext2d, Document, HtmlCanvasElement, ImageData, MouseEvent, Window,
 };
 
 pub struct Canvas {
     pub element: HtmlCanvasElement,
     pub context: CanvasRenderingContext2d,
-    pub pixels: Vec<Rc<Color>>,
+    pub layers: Vec<Layer>,
+    pub active_layer: usize,
     pub events: CanvasEvents,
-    body: HtmlElement,
+    pub viewport: Viewport,
+    pub overlay: Overlay,
+    selection: Option<Selection>,
+    floating: Option<Floating>,
+    window: Window,
+    /// Area that has changed since the last rendered frame.
+    dirty: Option<Rectangle>,
+    /// Pixels shown in place of a layer, given by its index, within the area they cover.
+    preview: Option<(usize, Surface, Rectangle)>,
+    frame: Option<Closure<dyn FnMut()>>,
+    is_frame_requested: bool,
 }
 
 impl Canvas {
@@ -34,99 +51,207 @@ impl Canvas {
             .unwrap()
             .dyn_into::<CanvasRenderingContext2d>()
             .unwrap();
+        let width = element.width();
+        let height = element.height();
         let channels = context
-            .get_image_data(
-                0_f64,
-                0_f64,
-                element.width() as f64,
-                element.height() as f64,
-            )
+            .get_image_data(0_f64, 0_f64, width as f64, height as f64)
             .unwrap()
             .data();
-        let mut pixels = Vec::with_capacity(channels.len() / 4);
-        for index in (0..channels.len()).step_by(4) {
-            let r = channels[index];
-            let g = channels[index + 1];
-            let b = channels[index + 2];
-            let a = channels[index + 3];
-            pixels.push(Rc::new(Color::new(r, g, b, a)));
-        }
+        let pixels = Surface::from_rgba(width, height, channels.0);
+        let layers = vec![Layer::new(String::from("Background"), pixels)];
         let events = CanvasEvents::new();
-        let body = document.body().unwrap();
+        let window = document.default_view().unwrap();
         Canvas {
             element,
             context,
-            pixels,
+            layers,
+            active_layer: 0,
             events,
-            body,
+            viewport: Viewport::new(),
+            overlay: Overlay::new(document),
+            selection: None,
+            floating: None,
+            window,
+            dirty: None,
+            preview: None,
+            frame: None,
+            is_frame_requested: false,
+        }
+    }
+
+    pub fn pixels(&self) -> &Surface {
+        &self.layers[self.active_layer].pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut Surface {
+        &mut self.layers[self.active_layer].pixels
+    }
+
+    pub fn is_active_layer_editable(&self) -> bool {
+        self.layers[self.active_layer].is_editable()
+    }
+
+    pub fn insert_layer(&mut self, idx: usize, layer: Layer) {
+        self.layers.insert(idx, layer);
+        self.active_layer = idx;
+        self.sync_clip();
+        self.refresh();
+    }
+
+    pub fn remove_layer(&mut self, idx: usize) -> Layer {
+        let layer = self.layers.remove(idx);
+        if self.active_layer > idx || self.active_layer == self.layers.len() {
+            self.active_layer -= 1;
         }
+        self.refresh();
+        layer
+    }
+
+    pub fn move_layer(&mut self, from: usize, to: usize) {
+        let layer = self.layers.remove(from);
+        self.layers.insert(to, layer);
+        self.active_layer = to;
+        self.refresh();
+    }
+
+    pub fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+
+    /// Constrains painting on every layer to the selection, or lifts the constraint on `None`.
+    pub fn set_selection(&mut self, selection: Option<Selection>) {
+        self.selection = selection;
+        self.sync_clip();
+        self.apply_viewport();
     }
 
+    fn sync_clip(&mut self) {
+        for layer in &mut self.layers {
+            layer.pixels.set_clip(self.selection);
+        }
+    }
+
+    /// Copy of the area of the active layer, transparent wherever the selection leaves it out.
+    pub fn extract_pixels(&self, area: &Rectangle) -> Surface {
+        let pixels = self.pixels();
+        let mut extracted = pixels.crop(&area.from, &area.to);
+        for y in area.from.y..=area.to.y {
+            for x in area.from.x..=area.to.x {
+                if !pixels.is_writable(&Point::new(x, y)) {
+                    let point = Point::new(x - area.from.x, y - area.from.y);
+                    extracted.set(&point, &Color::transparent());
+                }
+            }
+        }
+        extracted
+    }
+
+    pub fn floating(&self) -> Option<&Floating> {
+        self.floating.as_ref()
+    }
+
+    /// Shows the floating pixels over their layer and selects them, replacing whatever floated
+    /// before.
+    pub fn set_floating(&mut self, floating: Floating) {
+        let selection = floating
+            .area()
+            .clip(&self.element.width(), &self.element.height())
+            .map(Selection::new);
+        self.floating = Some(floating);
+        self.set_selection(selection);
+        self.show_floating();
+    }
+
+    /// Stops showing the floating pixels, handing them over to be either dropped or painted.
+    pub fn take_floating(&mut self) -> Option<Floating> {
+        let floating = self.floating.take();
+        if floating.is_some() {
+            self.discard_preview();
+        }
+        floating
+    }
+
+    fn show_floating(&mut self) {
+        let floating = match &self.floating {
+            Some(floating) => floating,
+            None => return,
+        };
+        let area = floating
+            .area()
+            .clip(&self.element.width(), &self.element.height());
+        match area {
+            Some(area) => {
+                let layer_idx = floating.layer_idx;
+                let mut pixels = self.layers[layer_idx].pixels.crop(&area.from, &area.to);
+                let at = Point::new(floating.at.x - area.from.x, floating.at.y - area.from.y);
+                pixels.draw(&floating.pixels, &at);
+                self.show_preview(layer_idx, pixels, area);
+            }
+            None => self.discard_preview(),
+        }
+    }
+
+    /// Maps client coordinates of the event to the pixel under the cursor, whatever the zoom and
+    /// pan of the viewport.
     pub fn get_point(&self, mouse_event: &MouseEvent) -> Point {
+        let (x, y) = self.get_position(mouse_event);
+        Point::new(x.floor() as i32, y.floor() as i32)
+    }
+
+    /// Like `get_point`, but keeps the fraction telling where within the pixel the cursor is.
+    pub fn get_position(&self, mouse_event: &MouseEvent) -> (f64, f64) {
         let rect = self.element.get_bounding_client_rect();
-        Point::new(
-            mouse_event.x() - rect.left() as i32,
-            mouse_event.y() - rect.top() as i32,
-        )
+        let x = (mouse_event.client_x() as f64 - rect.left()) * self.element.width() as f64
+            / rect.width();
+        let y = (mouse_event.client_y() as f64 - rect.top()) * self.element.height() as f64
+            / rect.height();
+        (x, y)
     }
 
-    pub fn get_segment(&self, prev: &Point, next: &Point) -> Option<Segment> {
-        let segment = Segment::new(prev.clone(), next.clone());
-        if self.is_segment_on_canvas(&segment) {
+    /// Clips the segment to the canvas enlarged by `margin` pixels on every side.
+    pub fn get_segment(&self, prev: &Point, next: &Point, margin: i32) -> Option<Segment> {
+        let segment = Segment::new(*prev, *next);
+        if self.is_point_within_margin(prev, margin) && self.is_point_within_margin(next, margin) {
             return Some(segment);
         }
         let min_x = prev.x.min(next.x);
         let max_x = prev.x.max(next.x);
         let min_y = prev.y.min(next.y);
         let max_y = prev.y.max(next.y);
-        let width = self.element.width() as i32;
-        let height = self.element.height() as i32;
-        if max_x < 0 || min_x >= width {
+        let from_bound = -margin;
+        let to_x_bound = self.element.width() as i32 - 1 + margin;
+        let to_y_bound = self.element.height() as i32 - 1 + margin;
+        if max_x < from_bound || min_x > to_x_bound {
             return None;
         }
-        if max_y < 0 || min_y >= height {
+        if max_y < from_bound || min_y > to_y_bound {
             return None;
         }
         if prev.x == next.x {
-            let mut from_y = min_y;
-            if from_y < 0 {
-                from_y = 0;
-            }
-            let mut to_y = max_y;
-            if to_y >= height {
-                to_y = height - 1;
-            }
             return Some(Segment::new(
-                Point::new(prev.x, from_y),
-                Point::new(next.x, to_y),
+                Point::new(prev.x, min_y.max(from_bound)),
+                Point::new(next.x, max_y.min(to_y_bound)),
             ));
         }
         if prev.y == next.y {
-            let mut from_x = min_x;
-            if from_x < 0 {
-                from_x = 0;
-            }
-            let mut to_x = max_x;
-            if to_x >= width {
-                to_x = width - 1;
-            }
             return Some(Segment::new(
-                Point::new(from_x, prev.y),
-                Point::new(to_x, next.y),
+                Point::new(min_x.max(from_bound), prev.y),
+                Point::new(max_x.min(to_x_bound), next.y),
             ));
         }
         let line = segment.as_line();
-        let top = Point::new(line.x(height - 1), height - 1);
-        let right = Point::new(width - 1, line.y(width - 1));
-        let bottom = Point::new(line.x(0), 0);
-        let left = Point::new(0, line.y(0));
+        let top = Point::new(line.x(to_y_bound), to_y_bound);
+        let right = Point::new(to_x_bound, line.y(to_x_bound));
+        let bottom = Point::new(line.x(from_bound), from_bound);
+        let left = Point::new(from_bound, line.y(from_bound));
         let potential_points = [prev, next, &top, &right, &bottom, &left];
         let mut points: Vec<&Point> = vec![];
         for potential_point in potential_points {
             if points.len() >= 2 {
                 break;
             }
-            if self.is_point_on_canvas(potential_point) && segment.is_point_within(potential_point)
+            if self.is_point_within_margin(potential_point, margin)
+                && segment.is_point_within(potential_point)
             {
                 points.push(potential_point);
             }
@@ -134,138 +259,234 @@ impl Canvas {
         if points.len() != 2 {
             return None;
         }
-        Some(Segment::new(points[0].clone(), points[1].clone()))
+        Some(Segment::new(*points[0], *points[1]))
     }
 
     pub fn is_point_on_canvas(&self, point: &Point) -> bool {
-        if point.x < 0 || point.x >= self.element.width() as i32 {
+        self.is_point_within_margin(point, 0)
+    }
+
+    pub fn is_point_within_margin(&self, point: &Point, margin: i32) -> bool {
+        if point.x < -margin || point.x >= self.element.width() as i32 + margin {
             return false;
         }
-        if point.y < 0 || point.y >= self.element.height() as i32 {
+        if point.y < -margin || point.y >= self.element.height() as i32 + margin {
             return false;
         }
         true
     }
 
-    pub fn is_segment_on_canvas(&self, segment: &Segment) -> bool {
-        self.is_point_on_canvas(&segment.a) && self.is_point_on_canvas(&segment.b)
+    pub fn pause_all_events(&mut self) {
+        self.events.is_paused = true;
     }
 
-    pub fn set_on_mouse_down(&mut self, event: Option<&Closure<dyn FnMut(MouseEvent)>>) {
-        let event = event.map(|e| e.as_ref().clone());
-        self.events.body_on_mouse_down = event;
-        self.resume_on_mouse_down();
+    pub fn resume_all_events(&mut self) {
+        self.events.is_paused = false;
     }
 
-    pub fn set_on_mouse_move(&mut self, event: Option<&Closure<dyn FnMut(MouseEvent)>>) {
-        let event = event.map(|e| e.as_ref().clone());
-        self.events.body_on_mouse_move = event;
-        self.resume_on_mouse_move();
+    /// Replaces the layers with ones of the given size. The selection and the floating pixels no
+    /// longer line up with the new pixels, so both are dropped.
+    pub fn resize(&mut self, width: u32, height: u32, layers: Vec<Layer>, active_layer: usize) {
+        self.element.set_width(width);
+        self.element.set_height(height);
+        self.selection = None;
+        self.floating = None;
+        self.apply_viewport();
+        self.layers = layers;
+        self.active_layer = active_layer;
+        self.sync_clip();
+        self.refresh();
     }
 
-    pub fn set_on_mouse_up(&mut self, event: Option<&Closure<dyn FnMut(MouseEvent)>>) {
-        let event = event.map(|e| e.as_ref().clone());
-        self.events.body_on_mouse_up = event;
-        self.resume_on_mouse_up();
+    /// Lays the canvas out according to the viewport and redraws the overlay to match.
+    pub fn apply_viewport(&self) {
+        self.viewport.apply(&self.element);
+        let handles = self.floating.as_ref().map(|floating| floating.area());
+        self.overlay.render(
+            &self.element,
+            &self.viewport,
+            self.selection.as_ref(),
+            handles.as_ref(),
+        );
     }
 
-    pub fn set_on_mouse_leave(&mut self, event: Option<&Closure<dyn FnMut(MouseEvent)>>) {
-        let event = event.map(|e| e.as_ref().clone());
-        self.events.body_on_mouse_leave = event;
-        self.resume_on_mouse_leave();
+    /// Makes the dashes outlining the selection march, if there is one, leaving the layout and the
+    /// rest of the overlay alone.
+    pub fn march_ants(&mut self) {
+        let selection = match &self.selection {
+            Some(selection) => selection,
+            None => return,
+        };
+        let handles = self.floating.as_ref().map(|floating| floating.area());
+        self.overlay
+            .march(&self.element, &self.viewport, selection, handles.as_ref());
     }
 
-    pub fn pause_all_events(&self) {
-        self.body.set_onmousedown(None);
-        self.body.set_onmousemove(None);
-        self.body.set_onmouseup(None);
-        self.body.set_onmouseleave(None);
+    /// Schedules the whole canvas to be rendered and drops any preview but the floating pixels.
+    pub fn refresh(&mut self) {
+        self.preview = None;
+        let width = self.element.width() as i32;
+        let height = self.element.height() as i32;
+        self.invalidate(&Rectangle::new(
+            Point::new(0, 0),
+            Point::new(width - 1, height - 1),
+        ));
+        self.show_floating();
     }
 
-    pub fn resume_all_events(&self) {
-        self.resume_on_mouse_down();
-        self.resume_on_mouse_move();
-        self.resume_on_mouse_up();
-        self.resume_on_mouse_leave();
+    /// Schedules the area to be rendered in the next animation frame. Areas invalidated before
+    /// the frame arrives are coalesced into one.
+    pub fn invalidate(&mut self, area: &Rectangle) {
+        self.dirty = Some(match &self.dirty {
+            Some(dirty) => dirty.union(area),
+            None => *area,
+        });
+        self.request_frame();
     }
 
-    pub fn extract_pixels(&self, from: &Point, to: &Point) -> Vec<Rc<Color>> {
-        let target_width = (from.x - to.x) as usize;
-        let target_height = (from.y - to.y) as usize;
-        let mut pixels = Vec::with_capacity(target_width * target_height);
-        for source_y in from.y..=to.y {
-            for source_x in from.x..=to.x {
-                let point = Point::new(source_x, source_y);
-                let idx = flat_idx(&point, &self.element.width());
-                pixels.push(Rc::clone(&self.pixels[idx]));
-            }
-        }
-        pixels
+    /// Shows `pixels` in place of the active layer until the preview is replaced or discarded.
+    /// They cover `area`, which bounds the pixels that differ from the active layer.
+    pub fn render_external_pixels(&mut self, pixels: Surface, area: Rectangle) {
+        self.show_preview(self.active_layer, pixels, area);
     }
 
-    pub fn resize(&mut self, width: u32, height: u32, mut pixels: Vec<Rc<Color>>) {
-        pixels.resize_with((width * height) as usize, || Rc::new(Color::white()));
-        self.element.set_width(width);
-        self.element.set_height(height);
-        self.pixels = pixels;
-        let image_data = self.create_image_data();
-        self.context
-            .put_image_data(&image_data, 0_f64, 0_f64)
-            .unwrap();
+    fn show_preview(&mut self, layer_idx: usize, pixels: Surface, area: Rectangle) {
+        self.discard_preview();
+        self.invalidate(&area);
+        self.preview = Some((layer_idx, pixels, area));
     }
 
-    pub fn refresh(&self) {
-        let image_data = self.create_image_data();
-        self.context
-            .put_image_data(&image_data, 0_f64, 0_f64)
-            .unwrap()
-    }
-
-    pub fn render_external_pixels(&self, pixels: &Vec<Rc<Color>>) {
-        let image_data = self.create_image_data_from_pixels(pixels);
-        self.context
-            .put_image_data(&image_data, 0_f64, 0_f64)
-            .unwrap()
+    /// Previews what `paint` draws over the active layer within `bounds`, copying only that part
+    /// of the layer. `paint` gets the copy along with the canvas position of its top left corner,
+    /// which it draws relative to, and returns the area that may have changed, if any.
+    pub fn render_preview(
+        &mut self,
+        bounds: &Rectangle,
+        paint: impl FnOnce(&mut Surface, &Point) -> Option<Rectangle>,
+    ) {
+        let area = match bounds.clip(&self.element.width(), &self.element.height()) {
+            Some(area) => area,
+            None => return self.discard_preview(),
+        };
+        let mut pixels = self.pixels().crop(&area.from, &area.to);
+        let clip = self
+            .selection
+            .map(|selection| selection.translate(-area.from.x, -area.from.y));
+        pixels.set_clip(clip);
+        match paint(&mut pixels, &area.from) {
+            Some(_) => self.render_external_pixels(pixels, area),
+            None => self.discard_preview(),
+        }
     }
 
-    fn create_image_data(&self) -> ImageData {
-        self.create_image_data_from_pixels(&self.pixels)
+    pub fn discard_preview(&mut self) {
+        if let Some((_, _, area)) = self.preview.take() {
+            self.invalidate(&area);
+        }
     }
 
-    fn create_image_data_from_pixels(&self, pixels: &Vec<Rc<Color>>) -> ImageData {
-        let width = self.element.width();
-        let data: Vec<u8> = pixels
-            .iter()
-            .flat_map(|pixel| [pixel.r, pixel.g, pixel.b, pixel.a])
-            .collect();
-        ImageData::new_with_u8_clamped_array(Clamped(&data), width).unwrap()
+    /// Renders the dirty area, uploading only that part of the image to the context.
+    pub fn render(&mut self) {
+        self.is_frame_requested = false;
+        let dirty = self.dirty.take();
+        let area = match dirty
+            .and_then(|dirty| dirty.clip(&self.element.width(), &self.element.height()))
+        {
+            Some(area) => area,
+            None => return,
+        };
+        let layer_idx = match &self.preview {
+            Some((layer_idx, _, _)) => *layer_idx,
+            None => self.active_layer,
+        };
+        let mut pixels = self.layers[layer_idx].pixels.crop(&area.from, &area.to);
+        if let Some((_, preview, preview_area)) = &self.preview {
+            let at = Point::new(
+                preview_area.from.x - area.from.x,
+                preview_area.from.y - area.from.y,
+            );
+            pixels.blit(preview, &at);
+        }
+        let composite = self.flatten_with(layer_idx, &pixels, &area);
+        let image_data = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(composite.data()),
+            area.width(),
+            area.height(),
+        )
+        .unwrap();
+        self.context
+            .put_image_data(&image_data, area.from.x as f64, area.from.y as f64)
+            .unwrap();
     }
 
-    fn resume_on_mouse_down(&self) {
-        let event = &self.events.body_on_mouse_down;
-        self.body
-            .set_onmousedown(event.as_ref().map(|e| e.unchecked_ref()));
+    fn request_frame(&mut self) {
+        if self.is_frame_requested {
+            return;
+        }
+        if let Some(frame) = &self.frame {
+            self.window
+                .request_animation_frame(frame.as_ref().unchecked_ref())
+                .unwrap();
+            self.is_frame_requested = true;
+        }
     }
 
-    fn resume_on_mouse_move(&self) {
-        let event = &self.events.body_on_mouse_move;
-        self.body
-            .set_onmousemove(event.as_ref().map(|e| e.unchecked_ref()));
+    /// RGBA channels of all visible layers composited together.
+    pub fn flatten(&self) -> Vec<u8> {
+        let width = self.element.width() as i32;
+        let height = self.element.height() as i32;
+        let area = Rectangle::new(Point::new(0, 0), Point::new(width - 1, height - 1));
+        self.composite(&area).data().to_vec()
     }
 
-    fn resume_on_mouse_up(&self) {
-        let event = &self.events.body_on_mouse_up;
-        self.body
-            .set_onmouseup(event.as_ref().map(|e| e.unchecked_ref()));
+    /// Composite of the area of all visible layers, as the canvas shows it without any preview.
+    pub fn composite(&self, area: &Rectangle) -> Surface {
+        let pixels = self.pixels().crop(&area.from, &area.to);
+        self.flatten_with(self.active_layer, &pixels, area)
     }
 
-    fn resume_on_mouse_leave(&self) {
-        let event = &self.events.body_on_mouse_leave;
-        self.body
-            .set_onmouseleave(event.as_ref().map(|e| e.unchecked_ref()));
+    /// Composite of the area of all visible layers, with `pixels`, which cover the area, standing
+    /// in for the layer at `pixels_idx`.
+    fn flatten_with(&self, pixels_idx: usize, pixels: &Surface, area: &Rectangle) -> Surface {
+        let mut composite: Option<Surface> = None;
+        for (layer_idx, layer) in self.layers.iter().enumerate() {
+            if !layer.visible {
+                continue;
+            }
+            let cropped;
+            let layer_pixels = if layer_idx == pixels_idx {
+                pixels
+            } else {
+                cropped = layer.pixels.crop(&area.from, &area.to);
+                &cropped
+            };
+            match &mut composite {
+                Some(composite) => composite.composite(layer_pixels, layer.opacity),
+                None if layer.opacity == 255 => composite = Some(layer_pixels.clone()),
+                None => {
+                    let mut transparent = Surface::new(area.width(), area.height());
+                    transparent.composite(layer_pixels, layer.opacity);
+                    composite = Some(transparent);
+                }
+            }
+        }
+        composite.unwrap_or_else(|| Surface::new(area.width(), area.height()))
     }
 }
 
 pub fn init(dom: Rc<RefCell<Dom>>) {
+    let frame = render(Rc::clone(&dom));
+    dom.borrow_mut().canvas.frame = Some(frame);
+    dom.borrow_mut().canvas.refresh();
+    canvas_events::init(Rc::clone(&dom));
+    viewport::init(Rc::clone(&dom));
+    overlay::init(Rc::clone(&dom));
     resizer::init(Rc::clone(&dom));
+    layer_panel::init(Rc::clone(&dom));
+}
+
+fn render(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        dom.borrow_mut().canvas.render();
+    })
 }
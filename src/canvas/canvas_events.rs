@@ -1,19 +1,148 @@
-use wasm_bindgen::JsValue;
+use std::cell::RefCell;
+use std::rc::Rc;
 
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, Event, PointerEvent};
+
+use crate::dom::Dom;
+use crate::tool::manager;
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Down,
+    Move,
+    Up,
+    Leave,
+    Cancel,
+    LostCapture,
+}
+
+const SECONDARY_BUTTON: i16 = 2;
+const SECONDARY_BUTTONS: u16 = 2;
+
+/// Tracks the pointer that drags over the canvas. Mouse, pen and touch are handled alike, but
+/// only one pointer at a time: while a pointer is pressed, the others are ignored so that a second
+/// finger does not scribble over the stroke of the first.
 pub struct CanvasEvents {
-    pub body_on_mouse_down: Option<JsValue>,
-    pub body_on_mouse_move: Option<JsValue>,
-    pub body_on_mouse_up: Option<JsValue>,
-    pub body_on_mouse_leave: Option<JsValue>,
+    pub is_paused: bool,
+    active_pointer: Option<i32>,
 }
 
 impl CanvasEvents {
     pub fn new() -> CanvasEvents {
         CanvasEvents {
-            body_on_mouse_down: None,
-            body_on_mouse_move: None,
-            body_on_mouse_up: None,
-            body_on_mouse_leave: None,
+            is_paused: false,
+            active_pointer: None,
         }
     }
+
+    /// What the active tool should make of the event, if anything. A drag is cancelled when the
+    /// right button is pressed during it, or when the browser takes the pointer away.
+    fn route(&mut self, kind: Kind, pointer_event: &PointerEvent) -> Option<Kind> {
+        if self.is_paused {
+            return None;
+        }
+        let pointer_id = pointer_event.pointer_id();
+        if self
+            .active_pointer
+            .is_some_and(|active_pointer| active_pointer != pointer_id)
+        {
+            return None;
+        }
+        let is_pressed = self.active_pointer.is_some();
+        match kind {
+            Kind::Down => {
+                self.active_pointer = Some(pointer_id);
+                Some(Kind::Down)
+            }
+            Kind::Move if is_pressed && is_right_click(pointer_event) => Some(Kind::Cancel),
+            Kind::Move => Some(Kind::Move),
+            // The captured pointer only leaves the viewport once it is released.
+            Kind::Leave if is_pressed => None,
+            Kind::Leave => Some(Kind::Leave),
+            Kind::Up | Kind::Cancel | Kind::LostCapture if !is_pressed => None,
+            Kind::Up | Kind::LostCapture => {
+                self.active_pointer = None;
+                Some(Kind::Up)
+            }
+            Kind::Cancel => {
+                self.active_pointer = None;
+                Some(Kind::Cancel)
+            }
+        }
+    }
+}
+
+/// Listens to pointer events on the viewport once and for all, passing them to whichever tool is
+/// active. Presses elsewhere, such as in the panels or dialogs, never reach the tools. A press
+/// captures the pointer, so the rest of the drag is followed even outside of the viewport.
+pub fn init(dom: Rc<RefCell<Dom>>) {
+    let viewport = dom.borrow().document.get_element_by_id("viewport").unwrap();
+    let events = [
+        ("pointerdown", Kind::Down),
+        ("pointermove", Kind::Move),
+        ("pointerup", Kind::Up),
+        ("pointercancel", Kind::Cancel),
+        ("pointerleave", Kind::Leave),
+        ("lostpointercapture", Kind::LostCapture),
+    ];
+    for (event, kind) in events {
+        let dispatch = dispatch(Rc::clone(&dom), kind);
+        viewport
+            .add_event_listener_with_callback(event, dispatch.as_ref().unchecked_ref())
+            .unwrap();
+        dispatch.forget();
+    }
+    // The right button paints with the secondary color instead of opening the context menu.
+    let on_context_menu = Closure::<dyn FnMut(Event)>::new(|event: Event| {
+        event.prevent_default();
+    });
+    viewport
+        .add_event_listener_with_callback("contextmenu", on_context_menu.as_ref().unchecked_ref())
+        .unwrap();
+    on_context_menu.forget();
+}
+
+fn dispatch(dom: Rc<RefCell<Dom>>, kind: Kind) -> Closure<dyn FnMut(PointerEvent)> {
+    Closure::wrap(Box::new(move |pointer_event: PointerEvent| {
+        let kind = dom.borrow_mut().canvas.events.route(kind, &pointer_event);
+        let pointer_event = &pointer_event;
+        match kind {
+            Some(Kind::Down) => {
+                capture(pointer_event);
+                manager::dispatch(&dom, |tool, dom| tool.on_press(dom, pointer_event))
+            }
+            Some(Kind::Move) => {
+                manager::dispatch(&dom, |tool, dom| tool.on_move(dom, pointer_event))
+            }
+            Some(Kind::Up) => {
+                manager::dispatch(&dom, |tool, dom| tool.on_release(dom, pointer_event))
+            }
+            Some(Kind::Leave) => manager::dispatch(&dom, |tool, dom| tool.on_leave(dom)),
+            Some(Kind::Cancel) => cancel(&dom),
+            Some(Kind::LostCapture) | None => {}
+        }
+    }) as Box<dyn FnMut(PointerEvent)>)
+}
+
+/// Cancels the drag in progress, if any.
+pub fn cancel(dom: &RefCell<Dom>) {
+    manager::dispatch(dom, |tool, dom| tool.on_cancel(dom));
+}
+
+/// Keeps sending the events of the pointer to the viewport until it is released.
+fn capture(pointer_event: &PointerEvent) {
+    let viewport = pointer_event
+        .current_target()
+        .unwrap()
+        .dyn_into::<Element>()
+        .unwrap();
+    // Capturing fails for a pointer that is no longer active, which then has nothing to follow.
+    let _ = viewport.set_pointer_capture(pointer_event.pointer_id());
+}
+
+/// Pressing another button while one is held shows up as a move rather than as a press.
+fn is_right_click(pointer_event: &PointerEvent) -> bool {
+    pointer_event.button() == SECONDARY_BUTTON && pointer_event.buttons() & SECONDARY_BUTTONS != 0
 }
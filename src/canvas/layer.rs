@@ -0,0 +1,30 @@
+use crate::canvas::surface::Surface;
+
+#[derive(Clone)]
+pub struct Layer {
+    pub name: String,
+    pub pixels: Surface,
+    pub visible: bool,
+    pub locked: bool,
+    pub opacity: u8,
+}
+
+impl Layer {
+    pub fn new(name: String, pixels: Surface) -> Layer {
+        Layer {
+            name,
+            pixels,
+            visible: true,
+            locked: false,
+            opacity: 255,
+        }
+    }
+
+    pub fn transparent(name: String, width: u32, height: u32) -> Layer {
+        Layer::new(name, Surface::new(width, height))
+    }
+
+    pub fn is_editable(&self) -> bool {
+        self.visible && !self.locked
+    }
+}
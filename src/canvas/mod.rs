@@ -1,2 +1,9 @@
+#[allow(clippy::module_inception)]
 pub mod canvas;
 pub mod canvas_events;
+pub mod layer;
+pub mod overlay;
+pub mod selection;
+pub mod surface;
+pub mod transform;
+pub mod viewport;
@@ -0,0 +1,275 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::Array;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    CanvasRenderingContext2d, Document, HtmlCanvasElement, HtmlInputElement, HtmlSelectElement,
+};
+
+use crate::canvas::selection::Selection;
+use crate::canvas::transform::Handle;
+use crate::canvas::viewport::Viewport;
+use crate::dom::Dom;
+use crate::rectangle::Rectangle;
+
+const PIXEL_LINE_COLOR: &str = "rgba(0,0,0,0.15)";
+const TILE_LINE_COLOR: &str = "rgba(0,0,0,0.5)";
+const ANTS_DASH: f64 = 4.0;
+const ANTS_INTERVAL: i32 = 100;
+/// Side of the square drawn for each handle, in screen pixels.
+pub const HANDLE_SIZE: f64 = 8.0;
+
+pub struct Grid {
+    pub is_enabled: bool,
+    /// Zoom from which the grid shows up.
+    pub min_zoom: f64,
+    /// Size, in pixels, of tiles outlined by a coarser grid.
+    pub tile: Option<u32>,
+}
+
+/// Screen sized canvas stacked over the viewport. Whatever is drawn on it never reaches the layers,
+/// so it never gets exported either.
+pub struct Overlay {
+    element: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+    pub grid: Grid,
+    /// Shifts the dashes of the selection outline so that they march.
+    ants_offset: f64,
+}
+
+impl Overlay {
+    pub fn new(document: &Document) -> Overlay {
+        let element = document
+            .get_element_by_id("overlay")
+            .unwrap()
+            .dyn_into::<HtmlCanvasElement>()
+            .unwrap();
+        let context = element
+            .get_context("2d")
+            .unwrap()
+            .unwrap()
+            .dyn_into::<CanvasRenderingContext2d>()
+            .unwrap();
+        let grid = Grid {
+            is_enabled: false,
+            min_zoom: 8.0,
+            tile: None,
+        };
+        Overlay {
+            element,
+            context,
+            grid,
+            ants_offset: 0.0,
+        }
+    }
+
+    pub fn render(
+        &self,
+        canvas: &HtmlCanvasElement,
+        viewport: &Viewport,
+        selection: Option<&Selection>,
+        handles: Option<&Rectangle>,
+    ) {
+        let parent = self.element.parent_element().unwrap();
+        self.element.set_width(parent.client_width() as u32);
+        self.element.set_height(parent.client_height() as u32);
+        if self.grid.is_enabled && viewport.zoom >= self.grid.min_zoom {
+            self.render_grid(canvas, viewport);
+        }
+        if let Some(selection) = selection {
+            self.render_selection(canvas, viewport, selection);
+        }
+        if let Some(area) = handles {
+            self.render_handles(canvas, viewport, area);
+        }
+    }
+
+    /// Shifts the dashes of the selection outline by a step. Only the outline and the handles lying
+    /// on it are stroked again: the solid white beneath the dashes covers the outline as it was,
+    /// so nothing needs clearing and the rest of the overlay stays as drawn.
+    pub fn march(
+        &mut self,
+        canvas: &HtmlCanvasElement,
+        viewport: &Viewport,
+        selection: &Selection,
+        handles: Option<&Rectangle>,
+    ) {
+        self.ants_offset = (self.ants_offset + 1.0) % (2.0 * ANTS_DASH);
+        self.render_selection(canvas, viewport, selection);
+        if let Some(area) = handles {
+            self.render_handles(canvas, viewport, area);
+        }
+    }
+
+    /// Draws the handles that scale the area, as black bordered white squares.
+    fn render_handles(&self, canvas: &HtmlCanvasElement, viewport: &Viewport, area: &Rectangle) {
+        let rect = self.element.get_bounding_client_rect();
+        let canvas_rect = canvas.get_bounding_client_rect();
+        self.context.set_line_width(1.0);
+        self.context.set_fill_style_str("white");
+        self.context.set_stroke_style_str("black");
+        for handle in Handle::ALL {
+            let (x, y) = handle.position(area);
+            let x =
+                (canvas_rect.left() - rect.left() + x * viewport.zoom - HANDLE_SIZE / 2.0).round();
+            let y =
+                (canvas_rect.top() - rect.top() + y * viewport.zoom - HANDLE_SIZE / 2.0).round();
+            self.context.fill_rect(x, y, HANDLE_SIZE, HANDLE_SIZE);
+            self.context
+                .stroke_rect(x + 0.5, y + 0.5, HANDLE_SIZE - 1.0, HANDLE_SIZE - 1.0);
+        }
+    }
+
+    /// Outlines the selected area with dashes alternating between black and white.
+    fn render_selection(
+        &self,
+        canvas: &HtmlCanvasElement,
+        viewport: &Viewport,
+        selection: &Selection,
+    ) {
+        let rect = self.element.get_bounding_client_rect();
+        let canvas_rect = canvas.get_bounding_client_rect();
+        let area = &selection.area;
+        let x = (canvas_rect.left() - rect.left() + area.from.x as f64 * viewport.zoom).round();
+        let y = (canvas_rect.top() - rect.top() + area.from.y as f64 * viewport.zoom).round();
+        let width = (area.width() as f64 * viewport.zoom).round();
+        let height = (area.height() as f64 * viewport.zoom).round();
+        self.context.set_line_width(1.0);
+        self.context.set_line_dash(&Array::new()).unwrap();
+        self.context.set_stroke_style_str("white");
+        self.context
+            .stroke_rect(x + 0.5, y + 0.5, width - 1.0, height - 1.0);
+        let dash = Array::of2(&ANTS_DASH.into(), &ANTS_DASH.into());
+        self.context.set_line_dash(&dash).unwrap();
+        self.context.set_line_dash_offset(-self.ants_offset);
+        self.context.set_stroke_style_str("black");
+        self.context
+            .stroke_rect(x + 0.5, y + 0.5, width - 1.0, height - 1.0);
+        self.context.set_line_dash(&Array::new()).unwrap();
+    }
+
+    fn render_grid(&self, canvas: &HtmlCanvasElement, viewport: &Viewport) {
+        self.render_lines(canvas, viewport.zoom, 1, PIXEL_LINE_COLOR);
+        if let Some(tile) = self.grid.tile {
+            self.render_lines(canvas, viewport.zoom, tile, TILE_LINE_COLOR);
+        }
+    }
+
+    /// Strokes lines between pixels of the canvas, every `step` pixels.
+    fn render_lines(&self, canvas: &HtmlCanvasElement, zoom: f64, step: u32, color: &str) {
+        let rect = self.element.get_bounding_client_rect();
+        let canvas_rect = canvas.get_bounding_client_rect();
+        let left = canvas_rect.left() - rect.left();
+        let top = canvas_rect.top() - rect.top();
+        let width = canvas.width();
+        let height = canvas.height();
+        let overlay_width = self.element.width() as f64;
+        let overlay_height = self.element.height() as f64;
+        let right = left + width as f64 * zoom;
+        let bottom = top + height as f64 * zoom;
+        self.context.begin_path();
+        for x in (0..=width).step_by(step as usize) {
+            let screen_x = (left + x as f64 * zoom).round() + 0.5;
+            if screen_x < 0.0 || screen_x > overlay_width {
+                continue;
+            }
+            self.context.move_to(screen_x, top.max(0.0));
+            self.context.line_to(screen_x, bottom.min(overlay_height));
+        }
+        for y in (0..=height).step_by(step as usize) {
+            let screen_y = (top + y as f64 * zoom).round() + 0.5;
+            if screen_y < 0.0 || screen_y > overlay_height {
+                continue;
+            }
+            self.context.move_to(left.max(0.0), screen_y);
+            self.context.line_to(right.min(overlay_width), screen_y);
+        }
+        self.context.set_stroke_style_str(color);
+        self.context.set_line_width(1.0);
+        self.context.stroke();
+    }
+}
+
+pub fn init(dom: Rc<RefCell<Dom>>) {
+    let on_input = update_grid(Rc::clone(&dom));
+    for id in ["grid-visible", "grid-zoom", "grid-tile"] {
+        dom.borrow()
+            .document
+            .get_element_by_id(id)
+            .unwrap()
+            .add_event_listener_with_callback("input", on_input.as_ref().unchecked_ref())
+            .unwrap();
+    }
+    on_input.forget();
+    let on_resize = render(Rc::clone(&dom));
+    dom.borrow()
+        .window
+        .add_event_listener_with_callback("resize", on_resize.as_ref().unchecked_ref())
+        .unwrap();
+    on_resize.forget();
+    let march = march(Rc::clone(&dom));
+    dom.borrow()
+        .window
+        .set_interval_with_callback_and_timeout_and_arguments_0(
+            march.as_ref().unchecked_ref(),
+            ANTS_INTERVAL,
+        )
+        .unwrap();
+    march.forget();
+    read_grid(&mut dom.borrow_mut());
+}
+
+fn update_grid(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        read_grid(&mut dom.borrow_mut());
+    })
+}
+
+fn render(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        dom.borrow().canvas.apply_viewport();
+    })
+}
+
+fn march(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        dom.borrow_mut().canvas.march_ants();
+    })
+}
+
+fn read_grid(dom: &mut Dom) {
+    let is_enabled = dom
+        .document
+        .get_element_by_id("grid-visible")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+        .checked();
+    let min_zoom = dom
+        .document
+        .get_element_by_id("grid-zoom")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+        .value()
+        .parse::<f64>()
+        .unwrap_or(800.0);
+    let tile = dom
+        .document
+        .get_element_by_id("grid-tile")
+        .unwrap()
+        .dyn_into::<HtmlSelectElement>()
+        .unwrap()
+        .value()
+        .parse::<u32>()
+        .ok()
+        .filter(|tile| *tile > 0);
+    dom.canvas.overlay.grid = Grid {
+        is_enabled,
+        min_zoom: min_zoom / 100.0,
+        tile,
+    };
+    dom.canvas.apply_viewport();
+}
@@ -0,0 +1,98 @@
+use crate::canvas::surface::Surface;
+use crate::canvas::transform::Transform;
+use crate::point::Point;
+use crate::rectangle::Rectangle;
+
+/// Region of the canvas that painting is constrained to: either the area itself or, when
+/// inverted, everything but the area.
+#[derive(Clone, Copy)]
+pub struct Selection {
+    pub area: Rectangle,
+    pub is_inverted: bool,
+}
+
+impl Selection {
+    pub fn new(area: Rectangle) -> Selection {
+        Selection {
+            area,
+            is_inverted: false,
+        }
+    }
+
+    pub fn inverted(&self) -> Selection {
+        Selection {
+            area: self.area,
+            is_inverted: !self.is_inverted,
+        }
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        self.area.contains(point) != self.is_inverted
+    }
+
+    /// The selection moved by `dx` and `dy`.
+    pub fn translate(&self, dx: i32, dy: i32) -> Selection {
+        Selection {
+            area: self.area.translate(dx, dy),
+            is_inverted: self.is_inverted,
+        }
+    }
+}
+
+/// Pixels hovering above a layer, not yet part of it, with their top left corner at `at`.
+pub struct Floating {
+    /// Pixels as shown, that is `source` once transformed.
+    pub pixels: Surface,
+    pub at: Point,
+    /// Index of the layer the pixels were lifted from or pasted onto, which they land on.
+    pub layer_idx: usize,
+    /// That layer as it was before the pixels were lifted off it, where the history step of the
+    /// lift begins. Pasted pixels have none.
+    pub base: Option<Surface>,
+    source: Surface,
+    transform: Transform,
+}
+
+impl Floating {
+    pub fn new(pixels: Surface, at: Point, layer_idx: usize) -> Floating {
+        Floating {
+            source: pixels.clone(),
+            transform: Transform::new(pixels.width(), pixels.height()),
+            pixels,
+            at,
+            layer_idx,
+            base: None,
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    /// Transforms the pixels as they were first floated, centering the result on `center`.
+    pub fn set_transform(&mut self, transform: Transform, center: (f64, f64)) {
+        self.pixels = transform.apply(&self.source);
+        self.transform = transform;
+        self.at = Point::new(
+            (center.0 - self.pixels.width() as f64 / 2.0).round() as i32,
+            (center.1 - self.pixels.height() as f64 / 2.0).round() as i32,
+        );
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (
+            self.at.x as f64 + self.pixels.width() as f64 / 2.0,
+            self.at.y as f64 + self.pixels.height() as f64 / 2.0,
+        )
+    }
+
+    pub fn area(&self) -> Rectangle {
+        Rectangle::new(
+            self.at,
+            Point::new(
+                self.at.x + self.pixels.width() as i32 - 1,
+                self.at.y + self.pixels.height() as i32 - 1,
+            ),
+        )
+    }
+}
@@ -0,0 +1,178 @@
+use crate::canvas::selection::Selection;
+use crate::color::Color;
+use crate::point::Point;
+use crate::util::flat_idx;
+
+/// Pixels packed row by row into a single RGBA buffer, 4 bytes per pixel.
+#[derive(Clone)]
+pub struct Surface {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+    /// Region outside of which `set` leaves pixels untouched.
+    clip: Option<Selection>,
+}
+
+impl Surface {
+    pub fn new(width: u32, height: u32) -> Surface {
+        Surface::filled(width, height, &Color::transparent())
+    }
+
+    pub fn filled(width: u32, height: u32, color: &Color) -> Surface {
+        Surface {
+            width,
+            height,
+            data: [color.r, color.g, color.b, color.a].repeat((width * height) as usize),
+            clip: None,
+        }
+    }
+
+    pub fn from_rgba(width: u32, height: u32, data: Vec<u8>) -> Surface {
+        assert_eq!(data.len(), (width * height * 4) as usize);
+        Surface {
+            width,
+            height,
+            data,
+            clip: None,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    /// RGBA channels of all pixels.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        point.x >= 0 && point.x < self.width as i32 && point.y >= 0 && point.y < self.height as i32
+    }
+
+    pub fn set_clip(&mut self, clip: Option<Selection>) {
+        self.clip = clip;
+    }
+
+    /// Whether `set` may change the pixel at the point.
+    pub fn is_writable(&self, point: &Point) -> bool {
+        match &self.clip {
+            Some(clip) => clip.contains(point),
+            None => true,
+        }
+    }
+
+    pub fn get(&self, point: &Point) -> Color {
+        self.get_idx(flat_idx(point, &self.width))
+    }
+
+    /// Paints the pixel at the point unless it lies outside of the clip.
+    pub fn set(&mut self, point: &Point, color: &Color) {
+        if self.is_writable(point) {
+            self.set_idx(flat_idx(point, &self.width), color);
+        }
+    }
+
+    /// Composites the color over the pixel at the point (source-over), unless the point lies
+    /// outside of the clip.
+    pub fn blend(&mut self, point: &Point, color: &Color) {
+        if color.a == 255 {
+            self.set(point, color);
+            return;
+        }
+        let color = color.over(&self.get(point), 255);
+        self.set(point, &color);
+    }
+
+    /// Color of the pixel at the given flat index.
+    pub fn get_idx(&self, idx: usize) -> Color {
+        let channels = &self.data[idx * 4..idx * 4 + 4];
+        Color::new(channels[0], channels[1], channels[2], channels[3])
+    }
+
+    /// Paints the pixel at the given flat index, regardless of the clip.
+    pub fn set_idx(&mut self, idx: usize, color: &Color) {
+        self.data[idx * 4..idx * 4 + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+    }
+
+    /// Copy of the area spanned by `from` and `to`, both inclusive.
+    pub fn crop(&self, from: &Point, to: &Point) -> Surface {
+        let width = (to.x - from.x + 1) as u32;
+        let height = (to.y - from.y + 1) as u32;
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for y in from.y..=to.y {
+            let row = flat_idx(&Point::new(from.x, y), &self.width) * 4;
+            data.extend_from_slice(&self.data[row..row + width as usize * 4]);
+        }
+        Surface::from_rgba(width, height, data)
+    }
+
+    /// Copies `source` into this surface with its top left corner placed at `at`. Parts falling
+    /// outside of this surface are dropped.
+    pub fn blit(&mut self, source: &Surface, at: &Point) {
+        let from_x = at.x.max(0);
+        let to_x = (at.x + source.width as i32).min(self.width as i32);
+        if from_x >= to_x {
+            return;
+        }
+        let length = (to_x - from_x) as usize * 4;
+        for source_y in 0..source.height as i32 {
+            let y = at.y + source_y;
+            if y < 0 || y >= self.height as i32 {
+                continue;
+            }
+            let source_idx = flat_idx(&Point::new(from_x - at.x, source_y), &source.width) * 4;
+            let target_idx = flat_idx(&Point::new(from_x, y), &self.width) * 4;
+            self.data[target_idx..target_idx + length]
+                .copy_from_slice(&source.data[source_idx..source_idx + length]);
+        }
+    }
+
+    /// Composites `source` over this surface with its top left corner placed at `at`. Unlike
+    /// `blit`, it honours the clip.
+    pub fn draw(&mut self, source: &Surface, at: &Point) {
+        for source_y in 0..source.height as i32 {
+            for source_x in 0..source.width as i32 {
+                let point = Point::new(at.x + source_x, at.y + source_y);
+                if !self.contains(&point) {
+                    continue;
+                }
+                let color = source.get(&Point::new(source_x, source_y));
+                if color.a == 0 {
+                    continue;
+                }
+                let color = color.over(&self.get(&point), 255);
+                self.set(&point, &color);
+            }
+        }
+    }
+
+    /// Composites `source`, weakened by `opacity`, over this surface (source-over).
+    pub fn composite(&mut self, source: &Surface, opacity: u8) {
+        let pixels = self
+            .data
+            .chunks_exact_mut(4)
+            .zip(source.data.chunks_exact(4));
+        for (target, pixel) in pixels {
+            if pixel[3] == 0 || opacity == 0 {
+                continue;
+            }
+            if pixel[3] == 255 && opacity == 255 {
+                target.copy_from_slice(pixel);
+                continue;
+            }
+            let background = Color::new(target[0], target[1], target[2], target[3]);
+            let color =
+                Color::new(pixel[0], pixel[1], pixel[2], pixel[3]).over(&background, opacity);
+            target.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+}
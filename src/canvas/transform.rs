@@ -0,0 +1,208 @@
+use crate::canvas::surface::Surface;
+use crate::color::Color;
+use crate::point::Point;
+use crate::rectangle::Rectangle;
+
+#[derive(Clone, Copy)]
+pub enum Resampling {
+    Nearest,
+    Bilinear,
+}
+
+/// How floating pixels are laid over the canvas: scaled to `width` by `height`, flipped, then
+/// rotated clockwise by `angle` degrees around their center.
+#[derive(Clone, Copy)]
+pub struct Transform {
+    pub width: u32,
+    pub height: u32,
+    pub angle: f64,
+    pub is_flipped_horizontally: bool,
+    pub is_flipped_vertically: bool,
+    pub resampling: Resampling,
+}
+
+impl Transform {
+    /// Transform leaving pixels of the given size as they are.
+    pub fn new(width: u32, height: u32) -> Transform {
+        Transform {
+            width,
+            height,
+            angle: 0.0,
+            is_flipped_horizontally: false,
+            is_flipped_vertically: false,
+            resampling: Resampling::Nearest,
+        }
+    }
+
+    /// Transformed copy of `source`, sized to the bounding box of the result. Each target pixel
+    /// is mapped back onto the source, so that the result has no holes.
+    pub fn apply(&self, source: &Surface) -> Surface {
+        if self.is_identity(source) {
+            return source.clone();
+        }
+        let width = self.width as f64;
+        let height = self.height as f64;
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let bounds_width = fit(width * cos.abs() + height * sin.abs());
+        let bounds_height = fit(width * sin.abs() + height * cos.abs());
+        let scale_x = source.width() as f64 / width;
+        let scale_y = source.height() as f64 / height;
+        let mut target = Surface::new(bounds_width, bounds_height);
+        for y in 0..bounds_height {
+            for x in 0..bounds_width {
+                let dx = x as f64 + 0.5 - bounds_width as f64 / 2.0;
+                let dy = y as f64 + 0.5 - bounds_height as f64 / 2.0;
+                let mut u = dx * cos + dy * sin + width / 2.0;
+                let mut v = -dx * sin + dy * cos + height / 2.0;
+                if u < 0.0 || u >= width || v < 0.0 || v >= height {
+                    continue;
+                }
+                if self.is_flipped_horizontally {
+                    u = width - u;
+                }
+                if self.is_flipped_vertically {
+                    v = height - v;
+                }
+                let color = match self.resampling {
+                    Resampling::Nearest => sample_nearest(source, u * scale_x, v * scale_y),
+                    Resampling::Bilinear => sample_bilinear(source, u * scale_x, v * scale_y),
+                };
+                target.set(&Point::new(x as i32, y as i32), &color);
+            }
+        }
+        target
+    }
+
+    fn is_identity(&self, source: &Surface) -> bool {
+        self.width == source.width()
+            && self.height == source.height()
+            && self.angle.rem_euclid(360.0) == 0.0
+            && !self.is_flipped_horizontally
+            && !self.is_flipped_vertically
+    }
+}
+
+/// Grips on the outline of floating pixels, dragged to scale them.
+#[derive(Clone, Copy)]
+pub enum Handle {
+    TopLeft,
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+}
+
+impl Handle {
+    pub const ALL: [Handle; 8] = [
+        Handle::TopLeft,
+        Handle::Top,
+        Handle::TopRight,
+        Handle::Right,
+        Handle::BottomRight,
+        Handle::Bottom,
+        Handle::BottomLeft,
+        Handle::Left,
+    ];
+
+    /// Position of the handle on the edges of the area, in canvas pixels.
+    pub fn position(&self, area: &Rectangle) -> (f64, f64) {
+        let pick = |side: i8, from: i32, to: i32| match side {
+            -1 => from as f64,
+            1 => (to + 1) as f64,
+            _ => (from + to + 1) as f64 / 2.0,
+        };
+        (
+            pick(self.horizontal_side(), area.from.x, area.to.x),
+            pick(self.vertical_side(), area.from.y, area.to.y),
+        )
+    }
+
+    /// The area with the edges held by the handle moved to `position`. Edges never cross each
+    /// other.
+    pub fn drag(&self, area: &Rectangle, position: (f64, f64)) -> Rectangle {
+        let x = position.0.round() as i32;
+        let y = position.1.round() as i32;
+        let mut from = area.from;
+        let mut to = area.to;
+        match self.horizontal_side() {
+            -1 => from.x = x.min(to.x),
+            1 => to.x = (x - 1).max(from.x),
+            _ => {}
+        }
+        match self.vertical_side() {
+            -1 => from.y = y.min(to.y),
+            1 => to.y = (y - 1).max(from.y),
+            _ => {}
+        }
+        Rectangle::new(from, to)
+    }
+
+    /// -1 for handles on the left edge, 1 for those on the right edge, 0 otherwise.
+    fn horizontal_side(&self) -> i8 {
+        match self {
+            Handle::TopLeft | Handle::Left | Handle::BottomLeft => -1,
+            Handle::TopRight | Handle::Right | Handle::BottomRight => 1,
+            Handle::Top | Handle::Bottom => 0,
+        }
+    }
+
+    /// -1 for handles on the top edge, 1 for those on the bottom edge, 0 otherwise.
+    fn vertical_side(&self) -> i8 {
+        match self {
+            Handle::TopLeft | Handle::Top | Handle::TopRight => -1,
+            Handle::BottomLeft | Handle::Bottom | Handle::BottomRight => 1,
+            Handle::Left | Handle::Right => 0,
+        }
+    }
+}
+
+/// Whole number of pixels covering the size, ignoring floating point noise.
+fn fit(size: f64) -> u32 {
+    (size - 1e-6).ceil().max(1.0) as u32
+}
+
+fn sample_nearest(source: &Surface, x: f64, y: f64) -> Color {
+    let x = (x as i32).min(source.width() as i32 - 1);
+    let y = (y as i32).min(source.height() as i32 - 1);
+    source.get(&Point::new(x, y))
+}
+
+/// Blends the four pixels around the point, weighting colors by their alpha so that transparent
+/// pixels do not darken the edges.
+fn sample_bilinear(source: &Surface, x: f64, y: f64) -> Color {
+    let x = (x - 0.5).max(0.0);
+    let y = (y - 0.5).max(0.0);
+    let x0 = (x as i32).min(source.width() as i32 - 1);
+    let y0 = (y as i32).min(source.height() as i32 - 1);
+    let x1 = (x0 + 1).min(source.width() as i32 - 1);
+    let y1 = (y0 + 1).min(source.height() as i32 - 1);
+    let fx = (x - x0 as f64).min(1.0);
+    let fy = (y - y0 as f64).min(1.0);
+    let samples = [
+        (x0, y0, (1.0 - fx) * (1.0 - fy)),
+        (x1, y0, fx * (1.0 - fy)),
+        (x0, y1, (1.0 - fx) * fy),
+        (x1, y1, fx * fy),
+    ];
+    let mut channels = [0.0; 4];
+    for (x, y, weight) in samples {
+        let color = source.get(&Point::new(x, y));
+        let alpha = color.a as f64 * weight;
+        channels[0] += color.r as f64 * alpha;
+        channels[1] += color.g as f64 * alpha;
+        channels[2] += color.b as f64 * alpha;
+        channels[3] += alpha;
+    }
+    if channels[3] == 0.0 {
+        return Color::transparent();
+    }
+    Color::new(
+        (channels[0] / channels[3]).round() as u8,
+        (channels[1] / channels[3]).round() as u8,
+        (channels[2] / channels[3]).round() as u8,
+        channels[3].round() as u8,
+    )
+}
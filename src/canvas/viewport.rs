@@ -0,0 +1,251 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    HtmlCanvasElement, HtmlElement, HtmlInputElement, KeyboardEvent, PointerEvent, WheelEvent,
+};
+
+use crate::dom::Dom;
+
+const ZOOM_LEVELS: [f64; 18] = [
+    0.1, 0.125, 0.167, 0.25, 0.333, 0.5, 0.667, 1.0, 1.5, 2.0, 3.0, 4.0, 6.0, 8.0, 12.0, 16.0,
+    24.0, 32.0,
+];
+const MIDDLE_BUTTON: i16 = 1;
+
+/// How the canvas is shown on the screen: scaled by `zoom` and shifted by `pan`, in screen pixels.
+pub struct Viewport {
+    pub zoom: f64,
+    pub pan_x: f64,
+    pub pan_y: f64,
+    is_space_held: bool,
+    /// Client coordinates of the last pointer event while panning.
+    pan_origin: Option<(i32, i32)>,
+}
+
+impl Viewport {
+    pub fn new() -> Viewport {
+        Viewport {
+            zoom: 1.0,
+            pan_x: 0.0,
+            pan_y: 0.0,
+            is_space_held: false,
+            pan_origin: None,
+        }
+    }
+
+    pub fn next_zoom(&self) -> f64 {
+        ZOOM_LEVELS
+            .iter()
+            .copied()
+            .find(|zoom| *zoom > self.zoom)
+            .unwrap_or(self.zoom)
+    }
+
+    pub fn previous_zoom(&self) -> f64 {
+        ZOOM_LEVELS
+            .iter()
+            .rev()
+            .copied()
+            .find(|zoom| *zoom < self.zoom)
+            .unwrap_or(self.zoom)
+    }
+
+    /// Sizes the canvas element and shifts its frame, the element holding the canvas and its
+    /// resizers. Pixels stay sharp when zoomed in.
+    pub fn apply(&self, element: &HtmlCanvasElement) {
+        let style = element.style();
+        style
+            .set_property(
+                "width",
+                &format!("{}px", element.width() as f64 * self.zoom),
+            )
+            .unwrap();
+        style
+            .set_property(
+                "height",
+                &format!("{}px", element.height() as f64 * self.zoom),
+            )
+            .unwrap();
+        let rendering = if self.zoom >= 1.0 {
+            "pixelated"
+        } else {
+            "auto"
+        };
+        style.set_property("image-rendering", rendering).unwrap();
+        let frame = element
+            .parent_element()
+            .unwrap()
+            .dyn_into::<HtmlElement>()
+            .unwrap();
+        frame
+            .style()
+            .set_property(
+                "transform",
+                &format!("translate({}px, {}px)", self.pan_x, self.pan_y),
+            )
+            .unwrap();
+    }
+}
+
+pub fn init(dom: Rc<RefCell<Dom>>) {
+    let viewport = viewport(&dom.borrow());
+    let on_wheel = on_wheel(Rc::clone(&dom));
+    viewport
+        .add_event_listener_with_callback("wheel", on_wheel.as_ref().unchecked_ref())
+        .unwrap();
+    on_wheel.forget();
+    let on_key = on_key(Rc::clone(&dom));
+    for event in ["keydown", "keyup"] {
+        dom.borrow()
+            .document
+            .add_event_listener_with_callback(event, on_key.as_ref().unchecked_ref())
+            .unwrap();
+    }
+    on_key.forget();
+    // Panning listens in the capture phase so that it takes pointer events away from tools.
+    let on_pointer_down = start_panning(Rc::clone(&dom));
+    let on_pointer_move = pan(Rc::clone(&dom));
+    let on_pointer_up = end_panning(Rc::clone(&dom));
+    let window = dom.borrow().window.clone();
+    window
+        .add_event_listener_with_callback_and_bool(
+            "pointerdown",
+            on_pointer_down.as_ref().unchecked_ref(),
+            true,
+        )
+        .unwrap();
+    window
+        .add_event_listener_with_callback_and_bool(
+            "pointermove",
+            on_pointer_move.as_ref().unchecked_ref(),
+            true,
+        )
+        .unwrap();
+    window
+        .add_event_listener_with_callback_and_bool(
+            "pointerup",
+            on_pointer_up.as_ref().unchecked_ref(),
+            true,
+        )
+        .unwrap();
+    on_pointer_down.forget();
+    on_pointer_move.forget();
+    on_pointer_up.forget();
+    show_zoom(&dom.borrow());
+}
+
+/// Zooms keeping the canvas point under the given client coordinates in place.
+pub fn zoom_at(dom: &mut Dom, zoom: f64, client_x: f64, client_y: f64) {
+    let canvas = &mut dom.canvas;
+    let rect = canvas.element.get_bounding_client_rect();
+    let x = (client_x - rect.left()) / canvas.viewport.zoom;
+    let y = (client_y - rect.top()) / canvas.viewport.zoom;
+    canvas.viewport.zoom = zoom;
+    canvas.apply_viewport();
+    let rect = canvas.element.get_bounding_client_rect();
+    canvas.viewport.pan_x += client_x - x * zoom - rect.left();
+    canvas.viewport.pan_y += client_y - y * zoom - rect.top();
+    canvas.apply_viewport();
+    show_zoom(dom);
+}
+
+fn viewport(dom: &Dom) -> HtmlElement {
+    dom.document
+        .get_element_by_id("viewport")
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
+}
+
+fn show_zoom(dom: &Dom) {
+    dom.document
+        .get_element_by_id("zoom-level")
+        .unwrap()
+        .set_text_content(Some(&format!(
+            "{}%",
+            (dom.canvas.viewport.zoom * 100.0).round()
+        )));
+}
+
+/// Zooms on vertical scrolls only. Horizontal ones, such as shift-scrolls and sideways trackpad
+/// swipes, have no vertical delta.
+fn on_wheel(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(WheelEvent)> {
+    Closure::wrap(Box::new(move |wheel_event: WheelEvent| {
+        if wheel_event.delta_y() == 0.0 {
+            return;
+        }
+        wheel_event.prevent_default();
+        let dom = &mut *dom.borrow_mut();
+        let zoom = if wheel_event.delta_y() < 0.0 {
+            dom.canvas.viewport.next_zoom()
+        } else {
+            dom.canvas.viewport.previous_zoom()
+        };
+        let client_x = wheel_event.client_x() as f64;
+        let client_y = wheel_event.client_y() as f64;
+        zoom_at(dom, zoom, client_x, client_y);
+    }) as Box<dyn FnMut(WheelEvent)>)
+}
+
+fn on_key(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(KeyboardEvent)> {
+    Closure::wrap(Box::new(move |keyboard_event: KeyboardEvent| {
+        if keyboard_event.key() != " " {
+            return;
+        }
+        let target = keyboard_event.target().unwrap();
+        if target.dyn_ref::<HtmlInputElement>().is_some() {
+            return;
+        }
+        keyboard_event.prevent_default();
+        let is_held = keyboard_event.type_() == "keydown";
+        dom.borrow_mut().canvas.viewport.is_space_held = is_held;
+        let cursor = if is_held {
+            "grab"
+        } else {
+            dom.borrow().tools.cursor()
+        };
+        viewport(&dom.borrow())
+            .style()
+            .set_property("cursor", cursor)
+            .unwrap();
+    }) as Box<dyn FnMut(KeyboardEvent)>)
+}
+
+fn start_panning(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(PointerEvent)> {
+    Closure::wrap(Box::new(move |pointer_event: PointerEvent| {
+        let viewport = &mut dom.borrow_mut().canvas.viewport;
+        if pointer_event.button() != MIDDLE_BUTTON && !viewport.is_space_held {
+            return;
+        }
+        pointer_event.prevent_default();
+        pointer_event.stop_propagation();
+        viewport.pan_origin = Some((pointer_event.client_x(), pointer_event.client_y()));
+    }) as Box<dyn FnMut(PointerEvent)>)
+}
+
+fn pan(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(PointerEvent)> {
+    Closure::wrap(Box::new(move |pointer_event: PointerEvent| {
+        let canvas = &mut dom.borrow_mut().canvas;
+        let (x, y) = match canvas.viewport.pan_origin {
+            Some(origin) => origin,
+            None => return,
+        };
+        pointer_event.stop_propagation();
+        canvas.viewport.pan_x += (pointer_event.client_x() - x) as f64;
+        canvas.viewport.pan_y += (pointer_event.client_y() - y) as f64;
+        canvas.viewport.pan_origin = Some((pointer_event.client_x(), pointer_event.client_y()));
+        canvas.apply_viewport();
+    }) as Box<dyn FnMut(PointerEvent)>)
+}
+
+fn end_panning(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(PointerEvent)> {
+    Closure::wrap(Box::new(move |pointer_event: PointerEvent| {
+        let viewport = &mut dom.borrow_mut().canvas.viewport;
+        if viewport.pan_origin.take().is_some() {
+            pointer_event.stop_propagation();
+        }
+    }) as Box<dyn FnMut(PointerEvent)>)
+}
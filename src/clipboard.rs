@@ -0,0 +1,269 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{Array, Function, Object, Promise, Reflect, Uint8Array};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, ClipboardItem, HtmlElement};
+
+use crate::canvas::selection::Floating;
+use crate::canvas::surface::Surface;
+use crate::color::Color;
+use crate::dom::Dom;
+use crate::image;
+use crate::point::Point;
+use crate::rectangle::Rectangle;
+use crate::tool::{manager, transform};
+
+const MIME_TYPE: &str = "image/png";
+
+pub fn init(dom: Rc<RefCell<Dom>>) {
+    let on_copy = Closure::<dyn FnMut()>::new({
+        let dom = Rc::clone(&dom);
+        move || copy(&mut dom.borrow_mut())
+    });
+    get_button(&dom.borrow(), "copy").set_onclick(Some(on_copy.as_ref().unchecked_ref()));
+    on_copy.forget();
+    let on_cut = Closure::<dyn FnMut()>::new({
+        let dom = Rc::clone(&dom);
+        move || cut(&mut dom.borrow_mut())
+    });
+    get_button(&dom.borrow(), "cut").set_onclick(Some(on_cut.as_ref().unchecked_ref()));
+    on_cut.forget();
+    let on_paste = Closure::<dyn FnMut()>::new({
+        let dom = Rc::clone(&dom);
+        move || paste(Rc::clone(&dom))
+    });
+    get_button(&dom.borrow(), "paste").set_onclick(Some(on_paste.as_ref().unchecked_ref()));
+    on_paste.forget();
+}
+
+/// Copies the selected pixels of the active layer, or the floating ones if there are any.
+pub fn copy(dom: &mut Dom) {
+    let pixels = match dom.canvas.floating() {
+        Some(floating) => floating.pixels.clone(),
+        None => dom.canvas.extract_pixels(&get_copied_area(dom)),
+    };
+    write(dom, &pixels);
+    dom.clipboard = Some(pixels);
+}
+
+/// Copies the selected pixels, then clears them to transparent. Floating pixels are just dropped,
+/// leaving the area they were lifted from cleared.
+pub fn cut(dom: &mut Dom) {
+    if dom.canvas.floating().is_some() {
+        copy(dom);
+        drop_floating(dom);
+        return;
+    }
+    if !dom.canvas.is_active_layer_editable() {
+        return;
+    }
+    copy(dom);
+    let area = get_copied_area(dom);
+    dom.history.begin(&dom.canvas);
+    clear(dom, &area);
+    dom.history.commit(&dom.canvas);
+}
+
+/// Cuts the selected area out of the active layer and floats it instead, so that it can be moved
+/// and transformed. Lifting and committing the pixels make up a single step of the history.
+pub fn lift(dom: &mut Dom) {
+    let area = match dom.canvas.selection() {
+        Some(selection) if !selection.is_inverted => selection.area,
+        _ => return,
+    };
+    if !dom.canvas.is_active_layer_editable() || dom.canvas.floating().is_some() {
+        return;
+    }
+    let pixels = dom.canvas.extract_pixels(&area);
+    let mut floating = Floating::new(pixels, area.from, dom.canvas.active_layer);
+    floating.base = Some(dom.canvas.pixels().clone());
+    clear(dom, &area);
+    dom.canvas.set_floating(floating);
+}
+
+/// Pastes the image held by the system clipboard, falling back to pixels copied within the app
+/// when there is none or the browser refuses to share it.
+pub fn paste(dom: Rc<RefCell<Dom>>) {
+    if !dom.borrow().canvas.is_active_layer_editable() {
+        return;
+    }
+    let clipboard = dom.borrow().window.navigator().clipboard();
+    if clipboard.is_undefined() {
+        paste_internal(&dom);
+        return;
+    }
+    let on_items = {
+        let dom = Rc::clone(&dom);
+        move |items: JsValue| {
+            let item = Array::from(&items)
+                .iter()
+                .map(|item| item.unchecked_into::<ClipboardItem>())
+                .find(|item| item.types().includes(&JsValue::from_str(MIME_TYPE), 0));
+            match item {
+                Some(item) => paste_item(dom, &item),
+                None => paste_internal(&dom),
+            }
+        }
+    };
+    then(clipboard.read(), on_items, fallback(dom));
+}
+
+/// Paints the floating pixels onto the layer they came with, whichever layer is active. They keep
+/// floating while that layer is hidden or locked.
+pub fn commit_floating(dom: &mut Dom) {
+    let layer_idx = match dom.canvas.floating() {
+        Some(floating) => floating.layer_idx,
+        None => return,
+    };
+    if !dom.canvas.layers[layer_idx].is_editable() {
+        return;
+    }
+    let mut floating = dom.canvas.take_floating().unwrap();
+    begin(dom, &mut floating);
+    dom.canvas.layers[layer_idx]
+        .pixels
+        .draw(&floating.pixels, &floating.at);
+    dom.history.commit(&dom.canvas);
+    dom.canvas.invalidate(&floating.area());
+}
+
+/// Drops the floating pixels. Lifted ones leave the area they were lifted from cleared.
+pub fn drop_floating(dom: &mut Dom) {
+    if let Some(mut floating) = dom.canvas.take_floating() {
+        begin(dom, &mut floating);
+        dom.history.commit(&dom.canvas);
+    }
+}
+
+/// Begins the history step that the floating pixels end: from before the lift for lifted pixels,
+/// from now on for pasted ones.
+fn begin(dom: &mut Dom, floating: &mut Floating) {
+    let base = match floating.base.take() {
+        Some(base) => base,
+        None => dom.canvas.layers[floating.layer_idx].pixels.clone(),
+    };
+    dom.history.begin_with(floating.layer_idx, base);
+}
+
+/// Clears the selected pixels of the area to transparent.
+fn clear(dom: &mut Dom, area: &Rectangle) {
+    let pixels = dom.canvas.pixels_mut();
+    for y in area.from.y..=area.to.y {
+        for x in area.from.x..=area.to.x {
+            pixels.set(&Point::new(x, y), &Color::transparent());
+        }
+    }
+    dom.canvas.invalidate(area);
+}
+
+/// The selected area, or the whole canvas when nothing or an inverted area is selected.
+fn get_copied_area(dom: &Dom) -> Rectangle {
+    match dom.canvas.selection() {
+        Some(selection) if !selection.is_inverted => selection.area,
+        _ => {
+            let width = dom.canvas.element.width() as i32;
+            let height = dom.canvas.element.height() as i32;
+            Rectangle::new(Point::new(0, 0), Point::new(width - 1, height - 1))
+        }
+    }
+}
+
+/// Puts the pixels on the system clipboard as a PNG image, if the browser allows it.
+fn write(dom: &Dom, pixels: &Surface) {
+    let clipboard = dom.window.navigator().clipboard();
+    if clipboard.is_undefined() {
+        return;
+    }
+    let bytes = image::encode_png(pixels.width(), pixels.height(), pixels.data());
+    let parts = Array::of1(&Uint8Array::from(bytes.as_slice()));
+    let options = BlobPropertyBag::new();
+    options.set_type(MIME_TYPE);
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options).unwrap();
+    let record = Object::new();
+    Reflect::set(&record, &JsValue::from_str(MIME_TYPE), &blob).unwrap();
+    if let Ok(item) = ClipboardItem::new_with_record_from_str_to_blob_promise(&record) {
+        then(clipboard.write(&Array::of1(&item)), |_| (), |_| ());
+    }
+}
+
+fn paste_item(dom: Rc<RefCell<Dom>>, item: &ClipboardItem) {
+    let on_blob = {
+        let dom = Rc::clone(&dom);
+        move |blob: JsValue| {
+            let on_buffer = {
+                let dom = Rc::clone(&dom);
+                move |buffer: JsValue| {
+                    let bytes = Uint8Array::new(&buffer).to_vec();
+                    match image::decode_png(&bytes) {
+                        Ok((width, height, channels)) => {
+                            float(&dom, Surface::from_rgba(width, height, channels))
+                        }
+                        Err(error) => dom
+                            .borrow()
+                            .window
+                            .alert_with_message(&format!("Cannot paste the image: {}", error))
+                            .unwrap(),
+                    }
+                }
+            };
+            let blob = blob.unchecked_into::<Blob>();
+            then(blob.array_buffer(), on_buffer, fallback(dom));
+        }
+    };
+    then(item.get_type(MIME_TYPE), on_blob, fallback(dom));
+}
+
+fn paste_internal(dom: &Rc<RefCell<Dom>>) {
+    let pixels = dom.borrow().clipboard.clone();
+    if let Some(pixels) = pixels {
+        float(dom, pixels);
+    }
+}
+
+fn fallback(dom: Rc<RefCell<Dom>>) -> impl FnOnce(JsValue) + 'static {
+    move |_| paste_internal(&dom)
+}
+
+/// Floats the pixels over the top left corner of the selection, or of the canvas, and switches to
+/// the transform tool so that they can be moved into place. Nothing is pasted while pixels already
+/// float that cannot land.
+fn float(dom: &Rc<RefCell<Dom>>, pixels: Surface) {
+    let dom = &mut *dom.borrow_mut();
+    commit_floating(dom);
+    if dom.canvas.floating().is_some() {
+        return;
+    }
+    let at = match dom.canvas.selection() {
+        Some(selection) if !selection.is_inverted => selection.area.from,
+        _ => Point::new(0, 0),
+    };
+    let floating = Floating::new(pixels, at, dom.canvas.active_layer);
+    dom.canvas.set_floating(floating);
+    manager::select(dom, "transform");
+    transform::show_angle(dom);
+}
+
+/// Runs one of the callbacks once the promise settles. The one that runs frees itself, so only the
+/// other one is left behind.
+fn then(
+    promise: Promise,
+    on_resolve: impl FnOnce(JsValue) + 'static,
+    on_reject: impl FnOnce(JsValue) + 'static,
+) {
+    let on_resolve = Closure::once_into_js(on_resolve);
+    let on_reject = Closure::once_into_js(on_reject);
+    let then = Reflect::get(&promise, &JsValue::from_str("then"))
+        .unwrap()
+        .unchecked_into::<Function>();
+    let _ = then.call2(&promise, &on_resolve, &on_reject);
+}
+
+fn get_button(dom: &Dom, id: &str) -> HtmlElement {
+    dom.document
+        .get_element_by_id(id)
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
+}
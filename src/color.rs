@@ -1,4 +1,4 @@
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -29,6 +29,131 @@ impl Color {
         }
     }
 
+    pub fn transparent() -> Color {
+        Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 0,
+        }
+    }
+
+    /// Composites this color, weakened by `opacity`, over `background` (source-over).
+    pub fn over(&self, background: &Color, opacity: u8) -> Color {
+        let src_a = self.a as u32 * opacity as u32 / 255;
+        if src_a == 0 {
+            return background.clone();
+        }
+        if src_a == 255 {
+            return Color::new(self.r, self.g, self.b, 255);
+        }
+        let dst_a = background.a as u32 * (255 - src_a) / 255;
+        let a = src_a + dst_a;
+        let channel = |src: u8, dst: u8| ((src as u32 * src_a + dst as u32 * dst_a) / a) as u8;
+        Color::new(
+            channel(self.r, background.r),
+            channel(self.g, background.g),
+            channel(self.b, background.b),
+            a as u8,
+        )
+    }
+
+    /// Color of the given hue in degrees, and saturation and value between 0 and 1.
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64, a: u8) -> Color {
+        let chroma = value * saturation;
+        Color::from_chroma(hue, chroma, value - chroma, a)
+    }
+
+    /// Hue in degrees, and saturation and value between 0 and 1.
+    pub fn to_hsv(&self) -> (f64, f64, f64) {
+        let (max, min) = self.extremes();
+        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (self.hue(), saturation, max)
+    }
+
+    /// Color of the given hue in degrees, and saturation and lightness between 0 and 1.
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64, a: u8) -> Color {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Color::from_chroma(hue, chroma, lightness - chroma / 2.0, a)
+    }
+
+    /// Hue in degrees, and saturation and lightness between 0 and 1.
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let (max, min) = self.extremes();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (self.hue(), saturation, lightness)
+    }
+
+    /// Parses `#rrggbb` or `#rgb`, the `#` being optional. The color is opaque.
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        let hex = hex.trim();
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !hex.chars().all(|char| char.is_ascii_hexdigit()) {
+            return None;
+        }
+        let hex = match hex.len() {
+            3 => hex.chars().flat_map(|char| [char, char]).collect(),
+            6 => hex.to_string(),
+            _ => return None,
+        };
+        let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).unwrap();
+        Some(Color::new(channel(0), channel(2), channel(4), 255))
+    }
+
+    /// Formats the color as `#rrggbb`, leaving alpha out.
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Color with the given hue and chroma, lifted by `m` on every channel.
+    fn from_chroma(hue: f64, chroma: f64, m: f64, a: u8) -> Color {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |value: f64| ((value + m).clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color::new(channel(r), channel(g), channel(b), a)
+    }
+
+    /// Hue in degrees, 0 for grays.
+    fn hue(&self) -> f64 {
+        let (max, min) = self.extremes();
+        let delta = max - min;
+        if delta == 0.0 {
+            return 0.0;
+        }
+        let [r, g, b] = self.units();
+        let hue = if max == r {
+            ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        };
+        hue * 60.0
+    }
+
+    /// Largest and smallest of the red, green and blue channels, between 0 and 1.
+    fn extremes(&self) -> (f64, f64) {
+        let [r, g, b] = self.units();
+        (r.max(g).max(b), r.min(g).min(b))
+    }
+
+    fn units(&self) -> [f64; 3] {
+        [self.r, self.g, self.b].map(|channel| channel as f64 / 255.0)
+    }
+
     pub fn as_css_value(&self) -> String {
         format!(
             "rgba({},{},{},{})",
@@ -1,4 +1,10 @@
 use crate::canvas::canvas::Canvas;
+use crate::canvas::surface::Surface;
+use crate::history::history::History;
+use crate::keymap::Keymap;
+use crate::palette::Palette;
+use crate::tool::color_picker::ColorPicker;
+use crate::tool::manager::ToolManager;
 use crate::tool::tool_bar::ToolBar;
 use web_sys::{window, Document, HtmlElement, Window};
 
@@ -7,7 +13,15 @@ pub struct Dom {
     pub document: Document,
     pub body: HtmlElement,
     pub tool_bar: ToolBar,
+    pub tools: ToolManager,
     pub canvas: Canvas,
+    pub history: History,
+    /// Pixels last copied or cut within the app.
+    pub clipboard: Option<Surface>,
+    pub keymap: Keymap,
+    pub palette: Palette,
+    /// Color picker, once it has been opened.
+    pub color_picker: Option<ColorPicker>,
 }
 
 impl Dom {
@@ -17,12 +31,20 @@ impl Dom {
         let body = document.body().unwrap();
         let canvas = Canvas::new(&document);
         let tool_bar = ToolBar::new(&document);
+        let history = History::new();
+        let keymap = Keymap::load(window.local_storage().ok().flatten().as_ref());
         Dom {
             window,
             document,
             body,
             tool_bar,
+            tools: ToolManager::new(),
             canvas,
+            history,
+            clipboard: None,
+            keymap,
+            palette: Palette::new(),
+            color_picker: None,
         }
     }
 }
@@ -0,0 +1,79 @@
+use crate::point::Point;
+use crate::rectangle::Rectangle;
+
+/// Ellipse inscribed in a bounding rectangle.
+pub struct Ellipse {
+    pub bounds: Rectangle,
+}
+
+impl Ellipse {
+    pub fn new(bounds: Rectangle) -> Self {
+        Ellipse { bounds }
+    }
+
+    /// Points of the outline rasterized with the midpoint algorithm, walking all four quadrants
+    /// at once. Works with bounding rectangles of even as well as odd size.
+    pub fn points(&self) -> Vec<Point> {
+        let a = (self.bounds.to.x - self.bounds.from.x) as i64;
+        let b = (self.bounds.to.y - self.bounds.from.y) as i64;
+        let b1 = b & 1;
+        let mut dx = 4 * (1 - a) * b * b;
+        let mut dy = 4 * (b1 + 1) * a * a;
+        let mut e = dx + dy + b1 * a * a;
+        let mut x0 = self.bounds.from.x;
+        let mut x1 = self.bounds.to.x;
+        let mut y0 = self.bounds.from.y + ((b + 1) / 2) as i32;
+        let mut y1 = y0 - b1 as i32;
+        let ddx = 8 * b * b;
+        let ddy = 8 * a * a;
+        let mut points = vec![];
+        loop {
+            points.push(Point::new(x1, y0));
+            points.push(Point::new(x0, y0));
+            points.push(Point::new(x0, y1));
+            points.push(Point::new(x1, y1));
+            let e2 = 2 * e;
+            if e2 <= dy {
+                y0 += 1;
+                y1 -= 1;
+                dy += ddy;
+                e += dy;
+            }
+            if e2 >= dx || 2 * e > dy {
+                x0 += 1;
+                x1 -= 1;
+                dx += ddx;
+                e += dx;
+            }
+            if x0 > x1 {
+                break;
+            }
+        }
+        // Flat ellipses stop too early, finish their tips.
+        while ((y0 - y1) as i64) <= b {
+            points.push(Point::new(x0 - 1, y0));
+            points.push(Point::new(x1 + 1, y0));
+            points.push(Point::new(x0 - 1, y1));
+            points.push(Point::new(x1 + 1, y1));
+            y0 += 1;
+            y1 -= 1;
+        }
+        points
+    }
+
+    /// Horizontal spans covering the interior and the outline, as `(y, from_x, to_x)`.
+    pub fn spans(&self) -> Vec<(i32, i32, i32)> {
+        let height = (self.bounds.to.y - self.bounds.from.y + 1) as usize;
+        let mut rows = vec![(i32::MAX, i32::MIN); height];
+        for point in self.points() {
+            let row = &mut rows[(point.y - self.bounds.from.y) as usize];
+            row.0 = row.0.min(point.x);
+            row.1 = row.1.max(point.x);
+        }
+        rows.into_iter()
+            .enumerate()
+            .filter(|(_, (from_x, to_x))| from_x <= to_x)
+            .map(|(row, (from_x, to_x))| (self.bounds.from.y + row as i32, from_x, to_x))
+            .collect()
+    }
+}
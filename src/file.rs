@@ -0,0 +1,136 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    Blob, BlobPropertyBag, DragEvent, Event, File, FileReader, HtmlAnchorElement, HtmlInputElement,
+    Url,
+};
+
+use crate::canvas::layer::Layer;
+use crate::canvas::surface::Surface;
+use crate::clipboard;
+use crate::dom::Dom;
+use crate::history::command::{CanvasChange, CanvasState, Command};
+use crate::image;
+use crate::layer_panel;
+
+const FILE_NAME: &str = "art-verse.png";
+
+pub fn init(dom: Rc<RefCell<Dom>>) {
+    let on_change = on_file_chosen(Rc::clone(&dom));
+    get_file_input(&dom.borrow()).set_onchange(Some(on_change.as_ref().unchecked_ref()));
+    on_change.forget();
+    let on_drag_over = Closure::<dyn FnMut(DragEvent)>::new(|drag_event: DragEvent| {
+        drag_event.prevent_default();
+    });
+    dom.borrow()
+        .canvas
+        .element
+        .set_ondragover(Some(on_drag_over.as_ref().unchecked_ref()));
+    on_drag_over.forget();
+    let on_drop = on_drop(Rc::clone(&dom));
+    dom.borrow()
+        .canvas
+        .element
+        .set_ondrop(Some(on_drop.as_ref().unchecked_ref()));
+    on_drop.forget();
+}
+
+pub fn open(dom: &Dom) {
+    get_file_input(dom).click();
+}
+
+pub fn save(dom: &Dom) {
+    let width = dom.canvas.element.width();
+    let height = dom.canvas.element.height();
+    let bytes = image::encode_png(width, height, &dom.canvas.flatten());
+    download(dom, &bytes, "image/png", FILE_NAME);
+}
+
+fn download(dom: &Dom, bytes: &[u8], mime_type: &str, file_name: &str) {
+    let parts = Array::of1(&Uint8Array::from(bytes));
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options).unwrap();
+    let url = Url::create_object_url_with_blob(&blob).unwrap();
+    let anchor = dom
+        .document
+        .create_element("a")
+        .unwrap()
+        .dyn_into::<HtmlAnchorElement>()
+        .unwrap();
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    Url::revoke_object_url(&url).unwrap();
+}
+
+fn on_file_chosen(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(Event)> {
+    Closure::wrap(Box::new(move |event: Event| {
+        let input = event
+            .target()
+            .unwrap()
+            .dyn_into::<HtmlInputElement>()
+            .unwrap();
+        if let Some(file) = input.files().and_then(|files| files.get(0)) {
+            read(Rc::clone(&dom), &file);
+        }
+        input.set_value("");
+    }) as Box<dyn FnMut(Event)>)
+}
+
+fn on_drop(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(DragEvent)> {
+    Closure::wrap(Box::new(move |drag_event: DragEvent| {
+        drag_event.prevent_default();
+        let file = drag_event
+            .data_transfer()
+            .and_then(|data_transfer| data_transfer.files())
+            .and_then(|files| files.get(0));
+        if let Some(file) = file {
+            read(Rc::clone(&dom), &file);
+        }
+    }) as Box<dyn FnMut(DragEvent)>)
+}
+
+fn read(dom: Rc<RefCell<Dom>>, file: &File) {
+    let reader = FileReader::new().unwrap();
+    let result_reader = reader.clone();
+    let on_load = Closure::once_into_js(move || {
+        let bytes = Uint8Array::new(&result_reader.result().unwrap()).to_vec();
+        match image::decode_png(&bytes) {
+            Ok((width, height, channels)) => open_image(&dom, width, height, channels),
+            Err(error) => dom
+                .borrow()
+                .window
+                .alert_with_message(&format!("Cannot open the image: {}", error))
+                .unwrap(),
+        }
+    });
+    reader.set_onload(Some(on_load.unchecked_ref()));
+    reader.read_as_array_buffer(file).unwrap();
+}
+
+fn open_image(dom: &RefCell<Dom>, width: u32, height: u32, channels: Vec<u8>) {
+    let pixels = Surface::from_rgba(width, height, channels);
+    let layers = vec![Layer::new(String::from("Background"), pixels)];
+    let dom = &mut *dom.borrow_mut();
+    clipboard::commit_floating(dom);
+    clipboard::drop_floating(dom);
+    let before = CanvasState::of(&dom.canvas);
+    dom.canvas.resize(width, height, layers, 0);
+    let after = CanvasState::of(&dom.canvas);
+    dom.history
+        .push(Command::Canvas(CanvasChange { before, after }));
+    layer_panel::render(dom);
+}
+
+fn get_file_input(dom: &Dom) -> HtmlInputElement {
+    dom.document
+        .get_element_by_id("open-file")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+}
@@ -0,0 +1,179 @@
+use crate::canvas::canvas::Canvas;
+use crate::canvas::layer::Layer;
+use crate::canvas::surface::Surface;
+use crate::color::Color;
+use crate::point::Point;
+use crate::rectangle::{self, Rectangle};
+use crate::util::flat_idx;
+
+pub enum Command {
+    Pixels(PixelsChange),
+    Canvas(CanvasChange),
+    AddLayer(LayerChange),
+    DeleteLayer(LayerChange),
+    MoveLayer(LayerMove),
+}
+
+impl Command {
+    pub fn undo(&self, canvas: &mut Canvas) {
+        match self {
+            Command::Pixels(change) => change.apply(canvas, |pixel| &pixel.before),
+            Command::Canvas(change) => change.before.apply(canvas),
+            Command::AddLayer(change) => change.remove(canvas),
+            Command::DeleteLayer(change) => change.insert(canvas),
+            Command::MoveLayer(change) => canvas.move_layer(change.to, change.from),
+        }
+    }
+
+    pub fn redo(&self, canvas: &mut Canvas) {
+        match self {
+            Command::Pixels(change) => change.apply(canvas, |pixel| &pixel.after),
+            Command::Canvas(change) => change.after.apply(canvas),
+            Command::AddLayer(change) => change.insert(canvas),
+            Command::DeleteLayer(change) => change.remove(canvas),
+            Command::MoveLayer(change) => canvas.move_layer(change.from, change.to),
+        }
+    }
+
+    /// Number of pixels kept alive by this command.
+    pub fn size(&self) -> usize {
+        match self {
+            Command::Pixels(change) => 2 * change.pixels.len(),
+            Command::Canvas(change) => change.before.size() + change.after.size(),
+            Command::AddLayer(change) | Command::DeleteLayer(change) => {
+                change.layer.pixels.pixel_count()
+            }
+            Command::MoveLayer(_) => 0,
+        }
+    }
+}
+
+pub struct PixelChange {
+    pub idx: usize,
+    pub before: Color,
+    pub after: Color,
+}
+
+pub struct PixelsChange {
+    pub layer_idx: usize,
+    pub pixels: Vec<PixelChange>,
+}
+
+impl PixelsChange {
+    pub fn between(layer_idx: usize, before: &Surface, after: &Surface) -> Option<PixelsChange> {
+        let pixels: Vec<PixelChange> = before
+            .data()
+            .chunks_exact(4)
+            .zip(after.data().chunks_exact(4))
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(idx, _)| PixelChange {
+                idx,
+                before: before.get_idx(idx),
+                after: after.get_idx(idx),
+            })
+            .collect();
+        if pixels.is_empty() {
+            return None;
+        }
+        Some(PixelsChange { layer_idx, pixels })
+    }
+
+    /// Like `between`, but only looks for changes within the area.
+    pub fn within(
+        layer_idx: usize,
+        before: &Surface,
+        after: &Surface,
+        area: &Rectangle,
+    ) -> Option<PixelsChange> {
+        let width = before.width();
+        let pixels: Vec<PixelChange> = (area.from.y..=area.to.y)
+            .flat_map(|y| (area.from.x..=area.to.x).map(move |x| Point::new(x, y)))
+            .map(|point| flat_idx(&point, &width))
+            .filter(|idx| before.get_idx(*idx) != after.get_idx(*idx))
+            .map(|idx| PixelChange {
+                idx,
+                before: before.get_idx(idx),
+                after: after.get_idx(idx),
+            })
+            .collect();
+        if pixels.is_empty() {
+            return None;
+        }
+        Some(PixelsChange { layer_idx, pixels })
+    }
+
+    fn apply(&self, canvas: &mut Canvas, color: fn(&PixelChange) -> &Color) {
+        let layer = &mut canvas.layers[self.layer_idx];
+        let width = layer.pixels.width() as usize;
+        let mut area: Option<Rectangle> = None;
+        for pixel in &self.pixels {
+            layer.pixels.set_idx(pixel.idx, color(pixel));
+            let point = Point::new((pixel.idx % width) as i32, (pixel.idx / width) as i32);
+            area = rectangle::union(area, Some(Rectangle::new(point, point)));
+        }
+        canvas.active_layer = self.layer_idx;
+        if let Some(area) = area {
+            canvas.invalidate(&area);
+        }
+    }
+}
+
+pub struct CanvasState {
+    pub width: u32,
+    pub height: u32,
+    pub layers: Vec<Layer>,
+    pub active_layer: usize,
+}
+
+impl CanvasState {
+    pub fn of(canvas: &Canvas) -> CanvasState {
+        CanvasState {
+            width: canvas.element.width(),
+            height: canvas.element.height(),
+            layers: canvas.layers.clone(),
+            active_layer: canvas.active_layer,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| layer.pixels.pixel_count())
+            .sum()
+    }
+
+    fn apply(&self, canvas: &mut Canvas) {
+        canvas.resize(
+            self.width,
+            self.height,
+            self.layers.clone(),
+            self.active_layer,
+        );
+    }
+}
+
+pub struct CanvasChange {
+    pub before: CanvasState,
+    pub after: CanvasState,
+}
+
+pub struct LayerChange {
+    pub idx: usize,
+    pub layer: Layer,
+}
+
+impl LayerChange {
+    fn insert(&self, canvas: &mut Canvas) {
+        canvas.insert_layer(self.idx, self.layer.clone());
+    }
+
+    fn remove(&self, canvas: &mut Canvas) {
+        canvas.remove_layer(self.idx);
+    }
+}
+
+pub struct LayerMove {
+    pub from: usize,
+    pub to: usize,
+}
@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use crate::canvas::canvas::Canvas;
+use crate::canvas::surface::Surface;
+use crate::clipboard;
+use crate::dom::Dom;
+use crate::history::command::{Command, PixelsChange};
+use crate::layer_panel;
+use crate::rectangle::Rectangle;
+
+const MAX_COMMANDS: usize = 100;
+const MAX_SIZE: usize = 16_000_000;
+
+pub struct History {
+    undo_stack: VecDeque<Command>,
+    redo_stack: Vec<Command>,
+    snapshot: Option<(usize, Surface)>,
+    size: usize,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            undo_stack: VecDeque::new(),
+            redo_stack: vec![],
+            snapshot: None,
+            size: 0,
+        }
+    }
+
+    /// Remembers pixels of the active layer so that the next `commit` can record what has changed
+    /// since.
+    pub fn begin(&mut self, canvas: &Canvas) {
+        self.begin_with(canvas.active_layer, canvas.pixels().clone());
+    }
+
+    /// Like `begin`, but remembers the given pixels for the layer at `layer_idx`, as they were
+    /// whenever the change began.
+    pub fn begin_with(&mut self, layer_idx: usize, snapshot: Surface) {
+        self.snapshot = Some((layer_idx, snapshot));
+    }
+
+    pub fn commit(&mut self, canvas: &Canvas) {
+        let snapshot = self.snapshot.take();
+        if let Some((layer_idx, snapshot)) = snapshot {
+            let pixels = &canvas.layers[layer_idx].pixels;
+            if let Some(change) = PixelsChange::between(layer_idx, &snapshot, pixels) {
+                self.push(Command::Pixels(change));
+            }
+        }
+    }
+
+    /// Like `commit`, but only looks for changes within the area, if anything may have changed at
+    /// all.
+    pub fn commit_within(&mut self, canvas: &Canvas, area: Option<Rectangle>) {
+        let snapshot = self.snapshot.take();
+        if let (Some((layer_idx, snapshot)), Some(area)) = (snapshot, area) {
+            let pixels = &canvas.layers[layer_idx].pixels;
+            if let Some(change) = PixelsChange::within(layer_idx, &snapshot, pixels, &area) {
+                self.push(Command::Pixels(change));
+            }
+        }
+    }
+
+    /// Puts back the pixels remembered by `begin`, dropping whatever has changed since.
+    pub fn rollback(&mut self, canvas: &mut Canvas) {
+        if let Some((layer_idx, snapshot)) = self.snapshot.take() {
+            canvas.layers[layer_idx].pixels = snapshot;
+            canvas.refresh();
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    /// Records the command, dropping the oldest ones past the limits. The newest command is always
+    /// kept, however large, so that it can be undone.
+    pub fn push(&mut self, command: Command) {
+        for command in self.redo_stack.drain(..) {
+            self.size -= command.size();
+        }
+        self.size += command.size();
+        self.undo_stack.push_back(command);
+        while self.undo_stack.len() > 1
+            && (self.undo_stack.len() > MAX_COMMANDS || self.size > MAX_SIZE)
+        {
+            match self.undo_stack.pop_front() {
+                Some(command) => self.size -= command.size(),
+                None => break,
+            }
+        }
+    }
+
+    pub fn undo(&mut self, canvas: &mut Canvas) {
+        if self.is_recording() {
+            return;
+        }
+        if let Some(command) = self.undo_stack.pop_back() {
+            command.undo(canvas);
+            self.redo_stack.push(command);
+        }
+    }
+
+    pub fn redo(&mut self, canvas: &mut Canvas) {
+        if self.is_recording() {
+            return;
+        }
+        if let Some(command) = self.redo_stack.pop() {
+            command.redo(canvas);
+            self.undo_stack.push_back(command);
+        }
+    }
+}
+
+/// Undoes the last step. Floating pixels are committed first, so that they are undone along with
+/// the rest rather than left floating over the pixels they were lifted from. Nothing is undone
+/// while they cannot be.
+pub fn undo(dom: &RefCell<Dom>) {
+    let dom = &mut *dom.borrow_mut();
+    clipboard::commit_floating(dom);
+    if dom.canvas.floating().is_some() {
+        return;
+    }
+    dom.history.undo(&mut dom.canvas);
+    layer_panel::render(dom);
+}
+
+/// Redoes the last undone step. Like any change, committing floating pixels first leaves nothing
+/// to redo, and nothing is redone while they cannot be committed.
+pub fn redo(dom: &RefCell<Dom>) {
+    let dom = &mut *dom.borrow_mut();
+    clipboard::commit_floating(dom);
+    if dom.canvas.floating().is_some() {
+        return;
+    }
+    dom.history.redo(&mut dom.canvas);
+    layer_panel::render(dom);
+}
@@ -0,0 +1,40 @@
+use png::{BitDepth, ColorType, Decoder, DecodingError, Encoder, Transformations};
+
+/// Encodes non-premultiplied RGBA channels as a PNG file.
+pub fn encode_png(width: u32, height: u32, channels: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut encoder = Encoder::new(&mut bytes, width, height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(channels).unwrap();
+    writer.finish().unwrap();
+    bytes
+}
+
+/// Decodes a PNG file into its width, height and non-premultiplied RGBA channels.
+pub fn decode_png(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), DecodingError> {
+    let mut decoder = Decoder::new(bytes);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+    let channels = match info.color_type {
+        ColorType::Rgba => buffer,
+        ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+            .collect(),
+        ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect(),
+        ColorType::Grayscale => buffer
+            .iter()
+            .flat_map(|value| [*value, *value, *value, 255])
+            .collect(),
+        ColorType::Indexed => unreachable!("Indexed colors are expanded by the decoder"),
+    };
+    Ok((info.width, info.height, channels))
+}
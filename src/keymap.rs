@@ -0,0 +1,542 @@
+use std::cell::RefCell;
+use std::fmt::{self, Display, Formatter};
+use std::rc::Rc;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    HtmlElement, HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement, KeyboardEvent,
+    MouseEvent, Storage,
+};
+
+use crate::canvas::canvas_events;
+use crate::clipboard;
+use crate::dom::Dom;
+use crate::file;
+use crate::history::history;
+use crate::palette;
+use crate::tool::select;
+
+const STORAGE_KEY: &str = "art-verse-keymap";
+const MODIFIER_KEYS: [&str; 4] = ["Control", "Shift", "Alt", "Meta"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Select,
+    Transform,
+    Pencil,
+    Line,
+    Rectangle,
+    Ellipse,
+    Eraser,
+    Bucket,
+    Eyedropper,
+    Color,
+    SwapColors,
+    Undo,
+    Redo,
+    Save,
+    Open,
+    Copy,
+    Cut,
+    Paste,
+    SelectAll,
+    Deselect,
+    InvertSelection,
+    CommitFloating,
+    CancelDrag,
+}
+
+impl Action {
+    pub const ALL: [Action; 23] = [
+        Action::Select,
+        Action::Transform,
+        Action::Pencil,
+        Action::Line,
+        Action::Rectangle,
+        Action::Ellipse,
+        Action::Eraser,
+        Action::Bucket,
+        Action::Eyedropper,
+        Action::Color,
+        Action::SwapColors,
+        Action::Undo,
+        Action::Redo,
+        Action::Save,
+        Action::Open,
+        Action::Copy,
+        Action::Cut,
+        Action::Paste,
+        Action::SelectAll,
+        Action::Deselect,
+        Action::InvertSelection,
+        Action::CommitFloating,
+        Action::CancelDrag,
+    ];
+
+    /// Identifier the action is persisted under. Tools share it with their tool bar element.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Action::Select => "select",
+            Action::Transform => "transform",
+            Action::Pencil => "pencil",
+            Action::Line => "line",
+            Action::Rectangle => "rectangle",
+            Action::Ellipse => "ellipse",
+            Action::Eraser => "eraser",
+            Action::Bucket => "bucket",
+            Action::Eyedropper => "eyedropper",
+            Action::Color => "color",
+            Action::SwapColors => "swap-colors",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Save => "save",
+            Action::Open => "open",
+            Action::Copy => "copy",
+            Action::Cut => "cut",
+            Action::Paste => "paste",
+            Action::SelectAll => "select-all",
+            Action::Deselect => "deselect",
+            Action::InvertSelection => "invert-selection",
+            Action::CommitFloating => "commit-floating",
+            Action::CancelDrag => "cancel-drag",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Select => "Select",
+            Action::Transform => "Transform",
+            Action::Pencil => "Pencil",
+            Action::Line => "Line",
+            Action::Rectangle => "Rectangle",
+            Action::Ellipse => "Ellipse",
+            Action::Eraser => "Eraser",
+            Action::Bucket => "Bucket",
+            Action::Eyedropper => "Eyedropper",
+            Action::Color => "Color picker",
+            Action::SwapColors => "Swap colors",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::Save => "Save",
+            Action::Open => "Open",
+            Action::Copy => "Copy",
+            Action::Cut => "Cut",
+            Action::Paste => "Paste",
+            Action::SelectAll => "Select all",
+            Action::Deselect => "Deselect",
+            Action::InvertSelection => "Invert selection",
+            Action::CommitFloating => "Drop pasted pixels",
+            Action::CancelDrag => "Cancel drag",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| action.id() == id)
+    }
+
+    fn is_tool(&self) -> bool {
+        matches!(
+            self,
+            Action::Select
+                | Action::Transform
+                | Action::Pencil
+                | Action::Line
+                | Action::Rectangle
+                | Action::Ellipse
+                | Action::Eraser
+                | Action::Bucket
+                | Action::Eyedropper
+                | Action::Color
+        )
+    }
+}
+
+/// Key pressed along with modifiers. Letters are kept lower case, and Meta counts as Ctrl.
+#[derive(Clone, PartialEq)]
+pub struct Shortcut {
+    pub key: String,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Shortcut {
+    pub fn of(keyboard_event: &KeyboardEvent) -> Shortcut {
+        Shortcut {
+            key: normalize_key(&keyboard_event.key()),
+            ctrl: keyboard_event.ctrl_key() || keyboard_event.meta_key(),
+            shift: keyboard_event.shift_key(),
+            alt: keyboard_event.alt_key(),
+        }
+    }
+
+    /// Parses shortcuts formatted like `Ctrl+Shift+Z`.
+    pub fn parse(text: &str) -> Option<Shortcut> {
+        let mut shortcut = Shortcut {
+            key: String::new(),
+            ctrl: false,
+            shift: false,
+            alt: false,
+        };
+        let mut parts: Vec<&str> = text.split('+').collect();
+        // A trailing empty part means that the key itself is `+`.
+        if text.ends_with('+') {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        let (key, modifiers) = parts.split_last()?;
+        for modifier in modifiers {
+            match *modifier {
+                "Ctrl" => shortcut.ctrl = true,
+                "Shift" => shortcut.shift = true,
+                "Alt" => shortcut.alt = true,
+                _ => return None,
+            }
+        }
+        if key.is_empty() {
+            return None;
+        }
+        shortcut.key = normalize_key(key);
+        Some(shortcut)
+    }
+
+    fn new(key: &str, ctrl: bool, shift: bool) -> Shortcut {
+        Shortcut {
+            key: key.to_string(),
+            ctrl,
+            shift,
+            alt: false,
+        }
+    }
+}
+
+impl Display for Shortcut {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(formatter, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(formatter, "Shift+")?;
+        }
+        if self.alt {
+            write!(formatter, "Alt+")?;
+        }
+        if self.key == " " {
+            write!(formatter, "Space")
+        } else if self.key.chars().count() == 1 {
+            write!(formatter, "{}", self.key.to_uppercase())
+        } else {
+            write!(formatter, "{}", self.key)
+        }
+    }
+}
+
+/// Which shortcuts trigger which actions. An action may have several shortcuts, but a shortcut
+/// triggers a single action.
+pub struct Keymap {
+    bindings: Vec<(Action, Shortcut)>,
+    /// Action waiting for the user to press its new shortcut.
+    recording: Option<Action>,
+}
+
+impl Keymap {
+    pub fn new() -> Keymap {
+        let bindings = vec![
+            (Action::Select, Shortcut::new("m", false, false)),
+            (Action::Transform, Shortcut::new("v", false, false)),
+            (Action::Pencil, Shortcut::new("b", false, false)),
+            (Action::Line, Shortcut::new("l", false, false)),
+            (Action::Rectangle, Shortcut::new("r", false, false)),
+            (Action::Ellipse, Shortcut::new("o", false, false)),
+            (Action::Eraser, Shortcut::new("e", false, false)),
+            (Action::Bucket, Shortcut::new("g", false, false)),
+            (Action::Eyedropper, Shortcut::new("i", false, false)),
+            (Action::Color, Shortcut::new("c", false, false)),
+            (Action::SwapColors, Shortcut::new("x", false, false)),
+            (Action::Undo, Shortcut::new("z", true, false)),
+            (Action::Redo, Shortcut::new("z", true, true)),
+            (Action::Redo, Shortcut::new("y", true, false)),
+            (Action::Save, Shortcut::new("s", true, false)),
+            (Action::Open, Shortcut::new("o", true, false)),
+            (Action::Copy, Shortcut::new("c", true, false)),
+            (Action::Cut, Shortcut::new("x", true, false)),
+            (Action::Paste, Shortcut::new("v", true, false)),
+            (Action::SelectAll, Shortcut::new("a", true, false)),
+            (Action::Deselect, Shortcut::new("d", true, false)),
+            (Action::InvertSelection, Shortcut::new("i", true, true)),
+            (Action::CommitFloating, Shortcut::new("Enter", false, false)),
+            (Action::CancelDrag, Shortcut::new("Escape", false, false)),
+        ];
+        Keymap {
+            bindings,
+            recording: None,
+        }
+    }
+
+    /// Default keymap with the bindings saved in the storage on top. Saved actions keep their
+    /// saved shortcuts only, while actions added since the keymap was saved keep their default
+    /// ones, unless a saved shortcut took them.
+    pub fn load(storage: Option<&Storage>) -> Keymap {
+        let mut keymap = Keymap::new();
+        let saved = storage.and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten());
+        let saved = saved
+            .iter()
+            .flat_map(|saved| saved.lines())
+            .filter_map(|line| {
+                let (id, shortcut) = line.split_once('=')?;
+                Some((Action::from_id(id)?, shortcut))
+            });
+        let mut saved_actions = vec![];
+        for (action, shortcut) in saved {
+            if !saved_actions.contains(&action) {
+                keymap.bindings.retain(|(bound, _)| *bound != action);
+                saved_actions.push(action);
+            }
+            if let Some(shortcut) = Shortcut::parse(shortcut) {
+                keymap
+                    .bindings
+                    .retain(|(_, bound_shortcut)| *bound_shortcut != shortcut);
+                keymap.bindings.push((action, shortcut));
+            }
+        }
+        keymap
+    }
+
+    /// Saves the keymap as lines of `action=shortcut`, with an empty shortcut for actions left
+    /// without any.
+    pub fn save(&self, storage: Option<&Storage>) {
+        let lines: Vec<String> = Action::ALL
+            .iter()
+            .flat_map(|action| {
+                let shortcuts = self.shortcuts(*action);
+                match shortcuts.is_empty() {
+                    true => vec![format!("{}=", action.id())],
+                    false => shortcuts
+                        .iter()
+                        .map(|shortcut| format!("{}={}", action.id(), shortcut))
+                        .collect(),
+                }
+            })
+            .collect();
+        if let Some(storage) = storage {
+            let _ = storage.set_item(STORAGE_KEY, &lines.join("\n"));
+        }
+    }
+
+    pub fn action(&self, shortcut: &Shortcut) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| bound == shortcut)
+            .map(|(action, _)| *action)
+    }
+
+    pub fn shortcuts(&self, action: Action) -> Vec<&Shortcut> {
+        self.bindings
+            .iter()
+            .filter(|(bound, _)| *bound == action)
+            .map(|(_, shortcut)| shortcut)
+            .collect()
+    }
+
+    /// Makes the shortcut the only one of the action, taking it away from any other action.
+    pub fn bind(&mut self, action: Action, shortcut: Shortcut) {
+        self.bindings
+            .retain(|(bound, bound_shortcut)| *bound != action && *bound_shortcut != shortcut);
+        self.bindings.push((action, shortcut));
+    }
+}
+
+pub fn init(dom: Rc<RefCell<Dom>>) {
+    let on_key_down = on_key_down(Rc::clone(&dom));
+    dom.borrow()
+        .document
+        .add_event_listener_with_callback("keydown", on_key_down.as_ref().unchecked_ref())
+        .unwrap();
+    on_key_down.forget();
+    let on_click = Closure::<dyn FnMut()>::new({
+        let dom = Rc::clone(&dom);
+        move || show_keymap(&dom.borrow(), true)
+    });
+    get_element(&dom.borrow(), "keymap").set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    let on_click = Closure::<dyn FnMut()>::new({
+        let dom = Rc::clone(&dom);
+        move || {
+            let dom = &mut *dom.borrow_mut();
+            dom.keymap.recording = None;
+            show_keymap(dom, false);
+        }
+    });
+    get_element(&dom.borrow(), "keymap-close").set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    let on_click = Closure::<dyn FnMut()>::new({
+        let dom = Rc::clone(&dom);
+        move || {
+            let dom = &mut *dom.borrow_mut();
+            dom.keymap = Keymap::new();
+            dom.keymap.save(get_storage(dom).as_ref());
+            render(dom);
+        }
+    });
+    get_element(&dom.borrow(), "keymap-reset").set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    let on_click = record(Rc::clone(&dom));
+    get_element(&dom.borrow(), "keymap-list").set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    render(&dom.borrow());
+}
+
+/// Local storage of the page, if the browser grants it.
+fn get_storage(dom: &Dom) -> Option<Storage> {
+    dom.window.local_storage().ok().flatten()
+}
+
+/// Lists every action with its shortcuts, each behind a button that records a new shortcut.
+fn render(dom: &Dom) {
+    let list = get_element(dom, "keymap-list");
+    list.set_inner_html("");
+    for action in Action::ALL {
+        let row = dom.document.create_element("div").unwrap();
+        row.set_class_name("flex items-center justify-between gap-4");
+        let name = dom.document.create_element("span").unwrap();
+        name.set_text_content(Some(action.name()));
+        row.append_child(&name).unwrap();
+        let button = dom.document.create_element("button").unwrap();
+        button.set_class_name("action keymap-shortcut px-2 border-2 rounded-lg");
+        button.set_attribute("data-action", action.id()).unwrap();
+        let label = if dom.keymap.recording == Some(action) {
+            String::from("Press keys…")
+        } else {
+            let shortcuts: Vec<String> = dom
+                .keymap
+                .shortcuts(action)
+                .iter()
+                .map(|shortcut| shortcut.to_string())
+                .collect();
+            match shortcuts.is_empty() {
+                true => String::from("None"),
+                false => shortcuts.join(", "),
+            }
+        };
+        button.set_text_content(Some(&label));
+        row.append_child(&button).unwrap();
+        list.append_child(&row).unwrap();
+    }
+}
+
+fn record(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(MouseEvent)> {
+    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
+        let action = mouse_event
+            .target()
+            .and_then(|target| target.dyn_into::<HtmlElement>().ok())
+            .and_then(|target| target.get_attribute("data-action"))
+            .and_then(|id| Action::from_id(&id));
+        if let Some(action) = action {
+            let dom = &mut *dom.borrow_mut();
+            dom.keymap.recording = Some(action);
+            render(dom);
+        }
+    }) as Box<dyn FnMut(MouseEvent)>)
+}
+
+/// Binds the pressed keys to the action being recorded, or runs the action they are bound to.
+fn on_key_down(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(KeyboardEvent)> {
+    Closure::wrap(Box::new(move |keyboard_event: KeyboardEvent| {
+        if MODIFIER_KEYS.contains(&keyboard_event.key().as_str()) {
+            return;
+        }
+        let shortcut = Shortcut::of(&keyboard_event);
+        let recording = dom.borrow_mut().keymap.recording.take();
+        if let Some(action) = recording {
+            keyboard_event.prevent_default();
+            let dom = &mut *dom.borrow_mut();
+            if shortcut.key != "Escape" {
+                dom.keymap.bind(action, shortcut);
+                dom.keymap.save(get_storage(dom).as_ref());
+            }
+            render(dom);
+            return;
+        }
+        if is_typing(&keyboard_event) {
+            return;
+        }
+        let action = dom.borrow().keymap.action(&shortcut);
+        if let Some(action) = action {
+            keyboard_event.prevent_default();
+            run(&dom, action);
+        }
+    }) as Box<dyn FnMut(KeyboardEvent)>)
+}
+
+/// Whether the keys go to a form field or to editable content rather than to the app.
+fn is_typing(keyboard_event: &KeyboardEvent) -> bool {
+    let target = match keyboard_event
+        .target()
+        .and_then(|target| target.dyn_into::<HtmlElement>().ok())
+    {
+        Some(target) => target,
+        None => return false,
+    };
+    target.is_content_editable()
+        || target.dyn_ref::<HtmlInputElement>().is_some()
+        || target.dyn_ref::<HtmlSelectElement>().is_some()
+        || target.dyn_ref::<HtmlTextAreaElement>().is_some()
+}
+
+fn run(dom: &Rc<RefCell<Dom>>, action: Action) {
+    if action.is_tool() {
+        // Tools are selected through their tool bar element, which also opens the color dialog.
+        let tool = get_element(&dom.borrow(), action.id());
+        tool.click();
+        return;
+    }
+    match action {
+        Action::SwapColors => palette::swap(&dom.borrow()),
+        Action::Undo => history::undo(dom),
+        Action::Redo => history::redo(dom),
+        Action::Save => file::save(&dom.borrow()),
+        Action::Open => file::open(&dom.borrow()),
+        Action::Copy => clipboard::copy(&mut dom.borrow_mut()),
+        Action::Cut => clipboard::cut(&mut dom.borrow_mut()),
+        Action::Paste => clipboard::paste(Rc::clone(dom)),
+        Action::SelectAll => select::select_all(&mut dom.borrow_mut()),
+        Action::Deselect => select::deselect(&mut dom.borrow_mut()),
+        Action::InvertSelection => select::invert(&mut dom.borrow_mut()),
+        Action::CommitFloating => clipboard::commit_floating(&mut dom.borrow_mut()),
+        Action::CancelDrag => canvas_events::cancel(dom),
+        _ => unreachable!("Tools are selected through their tool bar element"),
+    }
+}
+
+fn show_keymap(dom: &Dom, is_visible: bool) {
+    get_element(dom, "keymap-dialog")
+        .parent_element()
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
+        .style()
+        .set_property("visibility", if is_visible { "visible" } else { "hidden" })
+        .unwrap();
+    render(dom);
+}
+
+/// Letters are lower cased so that Shift does not change the key. `Space`, as shortcuts show the
+/// space bar, stands for the space itself.
+fn normalize_key(key: &str) -> String {
+    if key == "Space" {
+        String::from(" ")
+    } else if key.chars().count() == 1 {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
+}
+
+fn get_element(dom: &Dom, id: &str) -> HtmlElement {
+    dom.document
+        .get_element_by_id(id)
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
+}
@@ -0,0 +1,248 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, Event, HtmlElement, HtmlInputElement, MouseEvent};
+
+use crate::canvas::layer::Layer;
+use crate::clipboard;
+use crate::dom::Dom;
+use crate::history::command::{Command, LayerChange, LayerMove};
+
+pub fn init(dom: Rc<RefCell<Dom>>) {
+    let layer_list = layer_list(&dom.borrow());
+    let on_click = select_layer(Rc::clone(&dom));
+    layer_list.set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    let on_double_click = rename_layer(Rc::clone(&dom));
+    layer_list.set_ondblclick(Some(on_double_click.as_ref().unchecked_ref()));
+    on_double_click.forget();
+    let on_input = update_layer(Rc::clone(&dom));
+    layer_list.set_oninput(Some(on_input.as_ref().unchecked_ref()));
+    on_input.forget();
+    bind_action(Rc::clone(&dom), "add-layer", add_layer);
+    bind_action(Rc::clone(&dom), "duplicate-layer", duplicate_layer);
+    bind_action(Rc::clone(&dom), "delete-layer", delete_layer);
+    bind_action(Rc::clone(&dom), "move-layer-up", move_layer_up);
+    bind_action(Rc::clone(&dom), "move-layer-down", move_layer_down);
+    render(&dom.borrow());
+}
+
+pub fn render(dom: &Dom) {
+    let layer_list = layer_list(dom);
+    layer_list.set_inner_html("");
+    for (layer_idx, layer) in dom.canvas.layers.iter().enumerate() {
+        let row = create_layer_row(dom, layer_idx, layer);
+        if layer_idx == dom.canvas.active_layer {
+            row.class_list().add_1("selected").unwrap();
+        }
+        layer_list.append_child(&row).unwrap();
+    }
+}
+
+fn mark_active_layer(dom: &Dom) {
+    let rows = layer_list(dom).children();
+    for row_idx in 0..rows.length() {
+        let row = rows.item(row_idx).unwrap();
+        row.class_list()
+            .toggle_with_force("selected", row_idx as usize == dom.canvas.active_layer)
+            .unwrap();
+    }
+}
+
+fn layer_list(dom: &Dom) -> HtmlElement {
+    dom.document
+        .get_element_by_id("layer-list")
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
+}
+
+fn create_layer_row(dom: &Dom, layer_idx: usize, layer: &Layer) -> Element {
+    let row = dom.document.create_element("div").unwrap();
+    row.set_class_name("layer flex items-center gap-1 p-1 border-2 rounded-lg");
+    row.set_attribute("data-idx", &layer_idx.to_string())
+        .unwrap();
+    let visible = create_input(dom, "checkbox", "layer-visible");
+    visible.set_checked(layer.visible);
+    visible.set_title("Visible");
+    row.append_child(&visible).unwrap();
+    let locked = create_input(dom, "checkbox", "layer-locked");
+    locked.set_checked(layer.locked);
+    locked.set_title("Locked");
+    row.append_child(&locked).unwrap();
+    let name = dom.document.create_element("span").unwrap();
+    name.set_class_name("layer-name grow truncate");
+    name.set_text_content(Some(&layer.name));
+    row.append_child(&name).unwrap();
+    let opacity = create_input(dom, "range", "layer-opacity");
+    opacity.set_min("0");
+    opacity.set_max("255");
+    opacity.set_value(&layer.opacity.to_string());
+    opacity.set_title("Opacity");
+    opacity.set_class_name("layer-opacity w-16");
+    row.append_child(&opacity).unwrap();
+    row
+}
+
+fn create_input(dom: &Dom, input_type: &str, class_name: &str) -> HtmlInputElement {
+    let input = dom
+        .document
+        .create_element("input")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap();
+    input.set_type(input_type);
+    input.set_class_name(class_name);
+    input
+}
+
+fn get_layer_idx(event: &Event) -> Option<usize> {
+    event
+        .target()?
+        .dyn_into::<Element>()
+        .ok()?
+        .closest(".layer")
+        .ok()??
+        .get_attribute("data-idx")?
+        .parse::<usize>()
+        .ok()
+}
+
+fn select_layer(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(MouseEvent)> {
+    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
+        if let Some(layer_idx) = get_layer_idx(&mouse_event) {
+            dom.borrow_mut().canvas.active_layer = layer_idx;
+            mark_active_layer(&dom.borrow());
+        }
+    }) as Box<dyn FnMut(MouseEvent)>)
+}
+
+fn rename_layer(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(MouseEvent)> {
+    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
+        let layer_idx = match get_layer_idx(&mouse_event) {
+            Some(layer_idx) => layer_idx,
+            None => return,
+        };
+        let name = dom.borrow().canvas.layers[layer_idx].name.clone();
+        let name = dom
+            .borrow()
+            .window
+            .prompt_with_message_and_default("Layer name", &name)
+            .unwrap();
+        if let Some(name) = name.filter(|name| !name.trim().is_empty()) {
+            dom.borrow_mut().canvas.layers[layer_idx].name = name;
+            render(&dom.borrow());
+        }
+    }) as Box<dyn FnMut(MouseEvent)>)
+}
+
+fn update_layer(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(Event)> {
+    Closure::wrap(Box::new(move |event: Event| {
+        let layer_idx = match get_layer_idx(&event) {
+            Some(layer_idx) => layer_idx,
+            None => return,
+        };
+        let input = event
+            .target()
+            .unwrap()
+            .dyn_into::<HtmlInputElement>()
+            .unwrap();
+        write_layer(&input, &mut dom.borrow_mut().canvas.layers[layer_idx]);
+        dom.borrow_mut().canvas.refresh();
+    }) as Box<dyn FnMut(Event)>)
+}
+
+fn write_layer(input: &HtmlInputElement, layer: &mut Layer) {
+    match input.class_name().split(' ').next().unwrap() {
+        "layer-visible" => layer.visible = input.checked(),
+        "layer-locked" => layer.locked = input.checked(),
+        "layer-opacity" => layer.opacity = input.value().parse::<u8>().unwrap_or(255),
+        class_name => panic!("Unknown layer input {}", class_name),
+    }
+}
+
+fn bind_action(dom: Rc<RefCell<Dom>>, id: &str, action: fn(&RefCell<Dom>)) {
+    let button = dom
+        .borrow()
+        .document
+        .get_element_by_id(id)
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap();
+    let on_click = on_action(Rc::clone(&dom), action);
+    button.set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+}
+
+fn on_action(dom: Rc<RefCell<Dom>>, action: fn(&RefCell<Dom>)) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        // Floating pixels land on their layer before the layers shift around them, and the layers
+        // stay put while the pixels cannot land.
+        clipboard::commit_floating(&mut dom.borrow_mut());
+        if dom.borrow().canvas.floating().is_some() {
+            return;
+        }
+        action(&dom);
+        render(&dom.borrow());
+    })
+}
+
+fn add_layer(dom: &RefCell<Dom>) {
+    let Dom {
+        history, canvas, ..
+    } = &mut *dom.borrow_mut();
+    let idx = canvas.active_layer + 1;
+    let name = format!("Layer {}", canvas.layers.len() + 1);
+    let layer = Layer::transparent(name, canvas.pixels().width(), canvas.pixels().height());
+    canvas.insert_layer(idx, layer.clone());
+    history.push(Command::AddLayer(LayerChange { idx, layer }));
+}
+
+fn duplicate_layer(dom: &RefCell<Dom>) {
+    let Dom {
+        history, canvas, ..
+    } = &mut *dom.borrow_mut();
+    let idx = canvas.active_layer + 1;
+    let mut layer = canvas.layers[canvas.active_layer].clone();
+    layer.name = format!("{} copy", layer.name);
+    canvas.insert_layer(idx, layer.clone());
+    history.push(Command::AddLayer(LayerChange { idx, layer }));
+}
+
+fn delete_layer(dom: &RefCell<Dom>) {
+    let Dom {
+        history, canvas, ..
+    } = &mut *dom.borrow_mut();
+    if canvas.layers.len() <= 1 {
+        return;
+    }
+    let idx = canvas.active_layer;
+    let layer = canvas.remove_layer(idx);
+    history.push(Command::DeleteLayer(LayerChange { idx, layer }));
+}
+
+fn move_layer_up(dom: &RefCell<Dom>) {
+    let Dom {
+        history, canvas, ..
+    } = &mut *dom.borrow_mut();
+    let from = canvas.active_layer;
+    if from + 1 >= canvas.layers.len() {
+        return;
+    }
+    canvas.move_layer(from, from + 1);
+    history.push(Command::MoveLayer(LayerMove { from, to: from + 1 }));
+}
+
+fn move_layer_down(dom: &RefCell<Dom>) {
+    let Dom {
+        history, canvas, ..
+    } = &mut *dom.borrow_mut();
+    let from = canvas.active_layer;
+    if from == 0 {
+        return;
+    }
+    canvas.move_layer(from, from - 1);
+    history.push(Command::MoveLayer(LayerMove { from, to: from - 1 }));
+}
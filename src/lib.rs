@@ -6,11 +6,21 @@ use std::rc::Rc;
 use crate::dom::Dom;
 use wasm_bindgen::prelude::*;
 
+mod brush;
 mod canvas;
+mod clipboard;
 mod color;
 mod dom;
+mod ellipse;
+mod file;
+mod history;
+mod image;
+mod keymap;
+mod layer_panel;
 mod line;
+mod palette;
 mod point;
+mod rectangle;
 mod resizer;
 mod segment;
 mod tool;
@@ -28,5 +38,9 @@ pub fn entry_point() {
     let dom = Rc::new(RefCell::new(Dom::new()));
 
     tool::tool_bar::init(Rc::clone(&dom));
+    palette::init(Rc::clone(&dom));
     canvas::canvas::init(Rc::clone(&dom));
+    file::init(Rc::clone(&dom));
+    clipboard::init(Rc::clone(&dom));
+    keymap::init(Rc::clone(&dom));
 }
@@ -0,0 +1,87 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlElement;
+
+use crate::color::Color;
+use crate::dom::Dom;
+
+const SECONDARY_BUTTON: i16 = 2;
+
+/// Colors shared by the tools. The primary one is painted with the main button and the secondary
+/// one with the right button. The secondary one also fills shapes and the background uncovered
+/// when the canvas grows.
+#[derive(Clone)]
+pub struct Palette {
+    pub primary: Rc<RefCell<Color>>,
+    pub secondary: Rc<RefCell<Color>>,
+}
+
+impl Palette {
+    pub fn new() -> Palette {
+        Palette {
+            primary: Rc::new(RefCell::new(Color::black())),
+            secondary: Rc::new(RefCell::new(Color::white())),
+        }
+    }
+
+    /// The palette as seen from the pointer button: the secondary button swaps its colors.
+    pub fn for_button(&self, button: i16) -> Palette {
+        if button == SECONDARY_BUTTON {
+            Palette {
+                primary: Rc::clone(&self.secondary),
+                secondary: Rc::clone(&self.primary),
+            }
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Color painted with the pointer button.
+    pub fn pick(&self, button: i16) -> Rc<RefCell<Color>> {
+        self.for_button(button).primary
+    }
+
+    pub fn swap(&self) {
+        self.primary.swap(&self.secondary);
+    }
+}
+
+pub fn init(dom: Rc<RefCell<Dom>>) {
+    let on_click = Closure::<dyn FnMut()>::new({
+        let dom = Rc::clone(&dom);
+        move || swap(&dom.borrow())
+    });
+    get_element(&dom.borrow(), "swap-colors").set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    show(&dom.borrow());
+}
+
+pub fn swap(dom: &Dom) {
+    dom.palette.swap();
+    show(dom);
+}
+
+/// Shows both colors in their tool bar swatches.
+pub fn show(dom: &Dom) {
+    let swatches = [
+        ("primary-color", &dom.palette.primary),
+        ("secondary-color", &dom.palette.secondary),
+    ];
+    for (id, color) in swatches {
+        get_element(dom, id)
+            .style()
+            .set_property("background-color", &color.borrow().as_css_value())
+            .unwrap();
+    }
+}
+
+fn get_element(dom: &Dom, id: &str) -> HtmlElement {
+    dom.document
+        .get_element_by_id(id)
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
+}
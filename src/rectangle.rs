@@ -0,0 +1,106 @@
+use crate::point::Point;
+use crate::segment::Segment;
+
+#[derive(Clone, Copy)]
+pub struct Rectangle {
+    pub from: Point,
+    pub to: Point,
+}
+
+impl Rectangle {
+    /// Creates a rectangle spanned by two opposite corners given in any order.
+    pub fn new(a: Point, b: Point) -> Self {
+        Rectangle {
+            from: Point::new(a.x.min(b.x), a.y.min(b.y)),
+            to: Point::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    /// Creates a rectangle dragged from `anchor` to `cursor`, optionally constrained to a square
+    /// and optionally centered at `anchor`.
+    pub fn from_drag(anchor: &Point, cursor: &Point, square: bool, from_center: bool) -> Self {
+        let mut dx = cursor.x - anchor.x;
+        let mut dy = cursor.y - anchor.y;
+        if square {
+            let side = dx.abs().max(dy.abs());
+            dx = if dx < 0 { -side } else { side };
+            dy = if dy < 0 { -side } else { side };
+        }
+        let corner = Point::new(anchor.x + dx, anchor.y + dy);
+        if from_center {
+            return Rectangle::new(Point::new(anchor.x - dx, anchor.y - dy), corner);
+        }
+        Rectangle::new(*anchor, corner)
+    }
+
+    pub fn width(&self) -> u32 {
+        (self.to.x - self.from.x + 1) as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        (self.to.y - self.from.y + 1) as u32
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        point.x >= self.from.x
+            && point.x <= self.to.x
+            && point.y >= self.from.y
+            && point.y <= self.to.y
+    }
+
+    /// Smallest rectangle covering both rectangles.
+    pub fn union(&self, other: &Rectangle) -> Rectangle {
+        Rectangle {
+            from: Point::new(self.from.x.min(other.from.x), self.from.y.min(other.from.y)),
+            to: Point::new(self.to.x.max(other.to.x), self.to.y.max(other.to.y)),
+        }
+    }
+
+    pub fn edges(&self) -> [Segment; 4] {
+        let top_right = Point::new(self.to.x, self.from.y);
+        let bottom_left = Point::new(self.from.x, self.to.y);
+        [
+            Segment::new(self.from, top_right),
+            Segment::new(top_right, self.to),
+            Segment::new(self.to, bottom_left),
+            Segment::new(bottom_left, self.from),
+        ]
+    }
+
+    /// The rectangle moved by `dx` and `dy`.
+    pub fn translate(&self, dx: i32, dy: i32) -> Rectangle {
+        Rectangle {
+            from: Point::new(self.from.x + dx, self.from.y + dy),
+            to: Point::new(self.to.x + dx, self.to.y + dy),
+        }
+    }
+
+    /// The rectangle enlarged by `margin` pixels on every side.
+    pub fn expand(&self, margin: i32) -> Rectangle {
+        Rectangle {
+            from: Point::new(self.from.x - margin, self.from.y - margin),
+            to: Point::new(self.to.x + margin, self.to.y + margin),
+        }
+    }
+
+    /// Part of the rectangle that lies within a `width` x `height` area anchored at the origin.
+    pub fn clip(&self, width: &u32, height: &u32) -> Option<Rectangle> {
+        let from = Point::new(self.from.x.max(0), self.from.y.max(0));
+        let to = Point::new(
+            self.to.x.min(*width as i32 - 1),
+            self.to.y.min(*height as i32 - 1),
+        );
+        if from.x > to.x || from.y > to.y {
+            return None;
+        }
+        Some(Rectangle { from, to })
+    }
+}
+
+/// Union of two optional areas, `None` only when both are.
+pub fn union(a: Option<Rectangle>, b: Option<Rectangle>) -> Option<Rectangle> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union(&b)),
+        (a, b) => a.or(b),
+    }
+}
@@ -3,13 +3,16 @@ use std::rc::Rc;
 
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen::JsCast;
-use web_sys::{DomRect, Element, HtmlElement, MouseEvent};
+use web_sys::{DomRect, Element, HtmlElement, PointerEvent};
 
 use crate::canvas::canvas::Canvas;
+use crate::canvas::layer::Layer;
+use crate::canvas::surface::Surface;
+use crate::clipboard;
 use crate::color::Color;
 use crate::dom::Dom;
+use crate::history::command::{CanvasChange, CanvasState, Command};
 use crate::point::Point;
-use crate::util::flat_idx;
 
 pub fn init(dom: Rc<RefCell<Dom>>) {
     let resizers = dom.borrow().document.get_elements_by_class_name("resizer");
@@ -20,39 +23,42 @@ pub fn init(dom: Rc<RefCell<Dom>>) {
             .dyn_into::<HtmlElement>()
             .unwrap();
         let init_canvas_resize = init_canvas_resize(Rc::clone(&dom));
-        resizer.set_onmousedown(Some(init_canvas_resize.as_ref().unchecked_ref()));
+        resizer.set_onpointerdown(Some(init_canvas_resize.as_ref().unchecked_ref()));
         init_canvas_resize.forget();
     }
 }
 
-fn init_canvas_resize(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(MouseEvent)> {
-    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
-        mouse_event.prevent_default();
-        dom.borrow().canvas.pause_all_events();
+fn init_canvas_resize(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(PointerEvent)> {
+    Closure::wrap(Box::new(move |pointer_event: PointerEvent| {
+        pointer_event.prevent_default();
+        dom.borrow_mut().canvas.pause_all_events();
         let sketch = dom.borrow().document.get_element_by_id("canvas-sketch");
-        if let Some(_) = sketch {
+        if sketch.is_some() {
             return;
         }
-        let resizer = mouse_event.target().unwrap().dyn_into::<Element>().unwrap();
+        let resizer = pointer_event
+            .target()
+            .unwrap()
+            .dyn_into::<Element>()
+            .unwrap();
         let resizer_id = Rc::new(resizer.id());
         let canvas_rect = dom.borrow().canvas.element.get_bounding_client_rect();
         let sketch = Rc::new(create_canvas_sketch(&dom.borrow(), &canvas_rect));
-        dom.borrow().body.append_child(&**sketch).unwrap();
+        dom.borrow().body.append_child(&sketch).unwrap();
         let x_offset: i32;
         if resizer_id.contains("west") {
-            x_offset = canvas_rect.left() as i32 - mouse_event.x();
+            x_offset = canvas_rect.left() as i32 - pointer_event.x();
         } else if resizer_id.contains("east") {
-            x_offset = mouse_event.x() - canvas_rect.right() as i32;
+            x_offset = pointer_event.x() - canvas_rect.right() as i32;
         } else {
             x_offset = 0;
         }
         let x_offset = Rc::new(x_offset);
-        let y_offset: i32;
-        if resizer_id.contains("south") {
-            y_offset = mouse_event.y() - canvas_rect.bottom() as i32;
+        let y_offset = if resizer_id.contains("south") {
+            pointer_event.y() - canvas_rect.bottom() as i32
         } else {
-            y_offset = 0;
-        }
+            0
+        };
         let y_offset = Rc::new(y_offset);
         let resize_sketch = resize_canvas_sketch(
             Rc::clone(&dom),
@@ -63,18 +69,18 @@ fn init_canvas_resize(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut(MouseEvent)> {
         );
         dom.borrow()
             .body
-            .set_onmousemove(Some(resize_sketch.as_ref().unchecked_ref()));
+            .set_onpointermove(Some(resize_sketch.as_ref().unchecked_ref()));
         resize_sketch.forget();
         let resize_canvas =
             resize_canvas(Rc::clone(&dom), Rc::clone(&sketch), Rc::clone(&resizer_id));
         dom.borrow()
             .body
-            .set_onmouseup(Some(resize_canvas.as_ref().unchecked_ref()));
+            .set_onpointerup(Some(resize_canvas.as_ref().unchecked_ref()));
         dom.borrow()
             .body
-            .set_onmouseleave(Some(resize_canvas.as_ref().unchecked_ref()));
+            .set_onpointerleave(Some(resize_canvas.as_ref().unchecked_ref()));
         resize_canvas.forget();
-    }) as Box<dyn FnMut(MouseEvent)>)
+    }) as Box<dyn FnMut(PointerEvent)>)
 }
 
 fn create_canvas_sketch(dom: &Dom, rect: &DomRect) -> HtmlElement {
@@ -115,35 +121,32 @@ fn resize_canvas_sketch(
     resizer_id: Rc<String>,
     x_offset: Rc<i32>,
     y_offset: Rc<i32>,
-) -> Closure<dyn FnMut(MouseEvent)> {
-    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
+) -> Closure<dyn FnMut(PointerEvent)> {
+    Closure::wrap(Box::new(move |pointer_event: PointerEvent| {
         let mut height: Option<i32> = None;
         let mut left_diff: Option<i32> = None;
         let mut right_diff: Option<i32> = None;
         let rect = dom.borrow().canvas.element.get_bounding_client_rect();
         if resizer_id.contains("south") {
-            height = Some(mouse_event.y() - *y_offset - rect.y() as i32);
+            height = Some(pointer_event.y() - *y_offset - rect.y() as i32);
         }
         if resizer_id.contains("west") {
-            left_diff = Some(rect.left() as i32 - mouse_event.x() - *x_offset);
+            left_diff = Some(rect.left() as i32 - pointer_event.x() - *x_offset);
         } else if resizer_id.contains("east") {
-            right_diff = Some(mouse_event.x() - *x_offset - rect.right() as i32);
+            right_diff = Some(pointer_event.x() - *x_offset - rect.right() as i32);
         }
         let diff = left_diff.or(right_diff);
         let mut width: Option<i32> = None;
         let mut left: Option<f64> = None;
         let mut right: Option<f64> = None;
-        if diff.is_some() && diff.unwrap() >= 0 {
-            let diff = diff.unwrap();
+        if let Some(diff) = diff.filter(|diff| *diff >= 0) {
             width = Some(rect.width() as i32 + 2 * diff);
             left = Some(rect.left() - (width.unwrap() as f64 - rect.width()) / 2_f64);
-        } else if left_diff.is_some() {
-            let left_diff = left_diff.unwrap();
+        } else if let Some(left_diff) = left_diff {
             let window_width = dom.borrow().window.inner_width().unwrap().as_f64().unwrap();
             right = Some(window_width - rect.right());
             width = Some(rect.width() as i32 + left_diff);
-        } else if right_diff.is_some() {
-            let right_diff = right_diff.unwrap();
+        } else if let Some(right_diff) = right_diff {
             left = Some(rect.left());
             width = Some(rect.width() as i32 + right_diff);
         }
@@ -172,7 +175,7 @@ fn resize_canvas_sketch(
                 .set_property("right", format!("{r}px").as_str())
                 .unwrap();
         }
-    }) as Box<dyn FnMut(MouseEvent)>)
+    }) as Box<dyn FnMut(PointerEvent)>)
 }
 
 fn resize_canvas(
@@ -181,45 +184,88 @@ fn resize_canvas(
     resizer_id: Rc<String>,
 ) -> Closure<dyn FnMut()> {
     Closure::<dyn FnMut()>::new(move || {
+        // Floating pixels land before the resize rather than being dropped by it, unless their
+        // layer cannot take them.
+        clipboard::commit_floating(&mut dom.borrow_mut());
+        clipboard::drop_floating(&mut dom.borrow_mut());
         let sketch_rect = sketch.get_bounding_client_rect();
-        let sketch_width = sketch_rect.width() as u32;
-        let sketch_height = sketch_rect.height() as u32;
+        let zoom = dom.borrow().canvas.viewport.zoom;
+        let sketch_width = ((sketch_rect.width() / zoom).round() as u32).max(1);
+        let sketch_height = ((sketch_rect.height() / zoom).round() as u32).max(1);
         let (src_from, src_to, target_from) = resolve_canvas_points(
             &dom.borrow().canvas,
             &sketch_width,
             &sketch_height,
             &resizer_id,
         );
-        let src_width = src_to.x - src_from.x + 1;
-        let src_height = src_to.y - src_from.y + 1;
-        let src_pixels = dom.borrow().canvas.extract_pixels(&src_from, &src_to);
-        let mut target_pixels =
-            vec![Rc::new(Color::white()); (sketch_width * sketch_height) as usize];
-        for src_y in 0..src_height {
-            for src_x in 0..src_width {
-                let src_point = Point::new(src_x, src_y);
-                let src_idx = flat_idx(&src_point, &(src_width as u32));
-                let target_point = Point::new(target_from.x + src_x, target_from.y + src_y);
-                let target_idx = flat_idx(&target_point, &sketch_width);
-                target_pixels[target_idx] = Rc::clone(&src_pixels[src_idx]);
-            }
-        }
+        let layers_count = dom.borrow().canvas.layers.len();
+        let target_layers = (0..layers_count)
+            .map(|layer_idx| {
+                resize_layer(
+                    &dom.borrow(),
+                    layer_idx,
+                    &src_from,
+                    &src_to,
+                    &target_from,
+                    &sketch_width,
+                    &sketch_height,
+                )
+            })
+            .collect();
+        let before = CanvasState::of(&dom.borrow().canvas);
+        let active_layer = before.active_layer;
         dom.borrow_mut()
             .canvas
-            .resize(sketch_width, sketch_height, target_pixels);
+            .resize(sketch_width, sketch_height, target_layers, active_layer);
+        let after = CanvasState::of(&dom.borrow().canvas);
+        if before.width != after.width || before.height != after.height {
+            dom.borrow_mut()
+                .history
+                .push(Command::Canvas(CanvasChange { before, after }));
+        }
         sketch.remove();
-        dom.borrow().body.set_onmousemove(None);
-        dom.borrow().body.set_onmouseup(None);
-        dom.borrow().body.set_onmouseleave(None);
-        dom.borrow().canvas.resume_all_events();
+        dom.borrow().body.set_onpointermove(None);
+        dom.borrow().body.set_onpointerup(None);
+        dom.borrow().body.set_onpointerleave(None);
+        dom.borrow_mut().canvas.resume_all_events();
     })
 }
 
+/// Crops or grows the layer. The background grows with the secondary color, other layers grow
+/// transparent.
+fn resize_layer(
+    dom: &Dom,
+    layer_idx: usize,
+    src_from: &Point,
+    src_to: &Point,
+    target_from: &Point,
+    target_width: &u32,
+    target_height: &u32,
+) -> Layer {
+    let canvas = &dom.canvas;
+    let fill = if layer_idx == 0 {
+        dom.palette.secondary.borrow().clone()
+    } else {
+        Color::transparent()
+    };
+    let mut target_pixels = Surface::filled(*target_width, *target_height, &fill);
+    let src_pixels = canvas.layers[layer_idx].pixels.crop(src_from, src_to);
+    target_pixels.blit(&src_pixels, target_from);
+    let layer = &canvas.layers[layer_idx];
+    Layer {
+        name: layer.name.clone(),
+        pixels: target_pixels,
+        visible: layer.visible,
+        locked: layer.locked,
+        opacity: layer.opacity,
+    }
+}
+
 fn resolve_canvas_points(
     canvas: &Canvas,
     new_width: &u32,
     new_height: &u32,
-    resizer_id: &String,
+    resizer_id: &str,
 ) -> (Point, Point, Point) {
     let width = canvas.element.width() as i32;
     let height = canvas.element.height() as i32;
@@ -11,10 +11,65 @@ impl Segment {
         Segment { a, b }
     }
 
+    /// Segment dragged from `anchor` towards `cursor`. With a `step`, in degrees, the segment turns
+    /// to the closest multiple of it and reaches as far as the cursor projects onto it.
+    pub fn from_drag(anchor: &Point, cursor: &Point, step: Option<f64>) -> Self {
+        let step = match step {
+            Some(step) => step.to_radians(),
+            None => return Segment::new(*anchor, *cursor),
+        };
+        let dx = (cursor.x - anchor.x) as f64;
+        let dy = (cursor.y - anchor.y) as f64;
+        let angle = (dy.atan2(dx) / step).round() * step;
+        let (sin, cos) = angle.sin_cos();
+        let length = dx * cos + dy * sin;
+        let end = Point::new(
+            anchor.x + (length * cos).round() as i32,
+            anchor.y + (length * sin).round() as i32,
+        );
+        Segment::new(*anchor, end)
+    }
+
+    /// The segment moved by `dx` and `dy`.
+    pub fn translate(&self, dx: i32, dy: i32) -> Segment {
+        Segment::new(
+            Point::new(self.a.x + dx, self.a.y + dy),
+            Point::new(self.b.x + dx, self.b.y + dy),
+        )
+    }
+
     pub fn as_line(&self) -> Line {
         Line::from_points(&self.a, &self.b)
     }
 
+    /// Points of the segment rasterized with Bresenham's algorithm, from `a` to `b`.
+    pub fn points(&self) -> Vec<Point> {
+        let kx = if self.a.x <= self.b.x { 1 } else { -1 };
+        let ky = if self.a.y <= self.b.y { 1 } else { -1 };
+        let dx = (self.a.x - self.b.x).abs();
+        let dy = -(self.a.y - self.b.y).abs();
+        let mut e = dx + dy;
+        let mut e2: i32;
+        let mut point = self.a;
+        let mut points = vec![];
+        loop {
+            points.push(point);
+            if point.x == self.b.x && point.y == self.b.y {
+                break;
+            }
+            e2 = 2 * e;
+            if e2 >= dy {
+                e += dy;
+                point.x += kx;
+            }
+            if e2 <= dx {
+                e += dx;
+                point.y += ky;
+            }
+        }
+        points
+    }
+
     pub fn is_point_within(&self, point: &Point) -> bool {
         let min_x = self.a.x.min(self.b.x);
         let max_x = self.a.x.max(self.b.x);
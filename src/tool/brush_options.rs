@@ -0,0 +1,117 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlElement, HtmlInputElement, HtmlSelectElement};
+
+use crate::brush::{self, Bitmap, Brush, Tip};
+use crate::canvas::canvas::Canvas;
+use crate::dom::Dom;
+
+pub fn init(dom: Rc<RefCell<Dom>>, brush: Rc<RefCell<Brush>>) {
+    let custom_tip = Rc::new(RefCell::new(None));
+    let size_input = get_size_input(&dom.borrow());
+    size_input.set_min(&brush::MIN_SIZE.to_string());
+    size_input.set_max(&brush::MAX_SIZE.to_string());
+    size_input.set_value(&brush.borrow().size.to_string());
+    let on_input = update_brush(Rc::clone(&dom), Rc::clone(&brush), Rc::clone(&custom_tip));
+    size_input.set_oninput(Some(on_input.as_ref().unchecked_ref()));
+    get_tip_select(&dom.borrow()).set_oninput(Some(on_input.as_ref().unchecked_ref()));
+    on_input.forget();
+    let on_click = capture_tip(Rc::clone(&dom), Rc::clone(&brush), Rc::clone(&custom_tip));
+    dom.borrow()
+        .document
+        .get_element_by_id("brush-capture")
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
+        .set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+}
+
+fn update_brush(
+    dom: Rc<RefCell<Dom>>,
+    brush: Rc<RefCell<Brush>>,
+    custom_tip: Rc<RefCell<Option<Bitmap>>>,
+) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        write_brush(&dom.borrow(), &mut brush.borrow_mut(), &custom_tip.borrow());
+    })
+}
+
+fn capture_tip(
+    dom: Rc<RefCell<Dom>>,
+    brush: Rc<RefCell<Brush>>,
+    custom_tip: Rc<RefCell<Option<Bitmap>>>,
+) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        let bitmap = create_bitmap_from_layer(&dom.borrow().canvas);
+        if bitmap.is_none() {
+            return;
+        }
+        *custom_tip.borrow_mut() = bitmap;
+        get_tip_select(&dom.borrow()).set_value("custom");
+        write_brush(&dom.borrow(), &mut brush.borrow_mut(), &custom_tip.borrow());
+    })
+}
+
+fn write_brush(dom: &Dom, brush: &mut Brush, custom_tip: &Option<Bitmap>) {
+    let size = get_size_input(dom)
+        .value()
+        .parse::<u32>()
+        .unwrap_or(brush::MIN_SIZE);
+    let tip = match get_tip_select(dom).value().as_str() {
+        "round" => Tip::Round,
+        "square" => Tip::Square,
+        "calligraphy" => Tip::Bitmap(Bitmap::calligraphy()),
+        "spray" => Tip::Bitmap(Bitmap::spray()),
+        "custom" => match custom_tip {
+            Some(bitmap) => Tip::Bitmap(bitmap.clone()),
+            None => Tip::Round,
+        },
+        tip => panic!("Unknown brush tip {}", tip),
+    };
+    *brush = Brush::new(size, tip);
+}
+
+/// Creates a tip out of non-transparent pixels of the active layer, cropped to their bounds.
+fn create_bitmap_from_layer(canvas: &Canvas) -> Option<Bitmap> {
+    let pixels = canvas.pixels();
+    let width = pixels.width() as usize;
+    let painted: Vec<(usize, usize)> = (0..pixels.pixel_count())
+        .filter(|idx| pixels.get_idx(*idx).a > 0)
+        .map(|idx| (idx % width, idx / width))
+        .collect();
+    let min_x = painted.iter().map(|(x, _)| *x).min()?;
+    let max_x = painted.iter().map(|(x, _)| *x).max()?;
+    let min_y = painted.iter().map(|(_, y)| *y).min()?;
+    let max_y = painted.iter().map(|(_, y)| *y).max()?;
+    let mut mask = vec![];
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            mask.push(pixels.get_idx(y * width + x).a > 0);
+        }
+    }
+    Some(Bitmap::new(
+        (max_x - min_x + 1) as u32,
+        (max_y - min_y + 1) as u32,
+        mask,
+    ))
+}
+
+fn get_size_input(dom: &Dom) -> HtmlInputElement {
+    dom.document
+        .get_element_by_id("brush-size")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+}
+
+fn get_tip_select(dom: &Dom) -> HtmlSelectElement {
+    dom.document
+        .get_element_by_id("brush-tip")
+        .unwrap()
+        .dyn_into::<HtmlSelectElement>()
+        .unwrap()
+}
@@ -0,0 +1,156 @@
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlSelectElement, PointerEvent};
+
+use crate::canvas::surface::Surface;
+use crate::color::Color;
+use crate::dom::Dom;
+use crate::palette::Palette;
+use crate::point::Point;
+use crate::rectangle::{self, Rectangle};
+use crate::tool::tool::Tool;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+pub struct BucketTool {
+    palette: Palette,
+}
+
+impl BucketTool {
+    pub fn new(palette: Palette) -> BucketTool {
+        BucketTool { palette }
+    }
+}
+
+impl Tool for BucketTool {
+    fn id(&self) -> &'static str {
+        "bucket"
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["bucket-options"]
+    }
+
+    fn on_press(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        if !dom.canvas.is_active_layer_editable() {
+            return;
+        }
+        let point = dom.canvas.get_point(pointer_event);
+        if !dom.canvas.is_point_on_canvas(&point) {
+            return;
+        }
+        let (tolerance, connectivity) = read_options(dom);
+        dom.history.begin(&dom.canvas);
+        let color = self.palette.pick(pointer_event.button());
+        let area = put(
+            dom.canvas.pixels_mut(),
+            &point,
+            &color.borrow(),
+            tolerance,
+            connectivity,
+        );
+        if let Some(area) = area {
+            dom.canvas.invalidate(&area);
+        }
+        dom.history.commit(&dom.canvas);
+    }
+}
+
+/// Scanline flood fill of the region connected to `seed` whose colors differ from the seed color
+/// by at most `tolerance` on every channel, compositing the color over the region. Returns the
+/// area that may have changed.
+pub fn put(
+    pixels: &mut Surface,
+    seed: &Point,
+    color: &Color,
+    tolerance: u8,
+    connectivity: Connectivity,
+) -> Option<Rectangle> {
+    let width = pixels.width() as i32;
+    let height = pixels.height() as i32;
+    let target = pixels.get(seed);
+    let mut visited = vec![false; pixels.pixel_count()];
+    let matches = |pixels: &Surface, visited: &[bool], idx: usize| {
+        let point = Point::new(idx as i32 % width, idx as i32 / width);
+        !visited[idx]
+            && pixels.is_writable(&point)
+            && is_within_tolerance(&pixels.get_idx(idx), &target, tolerance)
+    };
+    let reach = if connectivity == Connectivity::Eight {
+        1
+    } else {
+        0
+    };
+    let mut bounds = None;
+    let mut seeds = vec![*seed];
+    while let Some(seed) = seeds.pop() {
+        let row = seed.y * width;
+        if !matches(pixels, &visited, (row + seed.x) as usize) {
+            continue;
+        }
+        let mut left = seed.x;
+        while left > 0 && matches(pixels, &visited, (row + left - 1) as usize) {
+            left -= 1;
+        }
+        let mut right = seed.x;
+        while right < width - 1 && matches(pixels, &visited, (row + right + 1) as usize) {
+            right += 1;
+        }
+        for x in left..=right {
+            pixels.blend(&Point::new(x, seed.y), color);
+            visited[(row + x) as usize] = true;
+        }
+        let span = Rectangle::new(Point::new(left, seed.y), Point::new(right, seed.y));
+        bounds = rectangle::union(bounds, Some(span));
+        let from_x = (left - reach).max(0);
+        let to_x = (right + reach).min(width - 1);
+        for y in [seed.y - 1, seed.y + 1] {
+            if y < 0 || y >= height {
+                continue;
+            }
+            let mut in_span = false;
+            for x in from_x..=to_x {
+                let is_match = matches(pixels, &visited, (y * width + x) as usize);
+                if is_match && !in_span {
+                    seeds.push(Point::new(x, y));
+                }
+                in_span = is_match;
+            }
+        }
+    }
+    bounds
+}
+
+fn is_within_tolerance(pixel: &Color, target: &Color, tolerance: u8) -> bool {
+    pixel.r.abs_diff(target.r) <= tolerance
+        && pixel.g.abs_diff(target.g) <= tolerance
+        && pixel.b.abs_diff(target.b) <= tolerance
+        && pixel.a.abs_diff(target.a) <= tolerance
+}
+
+fn read_options(dom: &Dom) -> (u8, Connectivity) {
+    let tolerance = dom
+        .document
+        .get_element_by_id("bucket-tolerance")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+        .value()
+        .parse::<u8>()
+        .unwrap_or(0);
+    let connectivity = dom
+        .document
+        .get_element_by_id("bucket-connectivity")
+        .unwrap()
+        .dyn_into::<HtmlSelectElement>()
+        .unwrap()
+        .value();
+    let connectivity = match connectivity.as_str() {
+        "8" => Connectivity::Eight,
+        _ => Connectivity::Four,
+    };
+    (tolerance, connectivity)
+}
@@ -1,130 +1,365 @@
 use crate::color::Color;
 use crate::dom::Dom;
-use std::cell::RefCell;
-use std::collections::LinkedList;
+use crate::palette;
+use std::cell::{Cell, RefCell};
+use std::f64::consts::PI;
 use std::rc::Rc;
 use wasm_bindgen::closure::Closure;
-use wasm_bindgen::JsCast;
-use web_sys::{HtmlCollection, HtmlElement, HtmlInputElement, InputEvent};
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, HtmlElement, HtmlInputElement, ImageData,
+    PointerEvent,
+};
+
+const RGB_INPUTS: [&str; 3] = [
+    "color-picker-red",
+    "color-picker-green",
+    "color-picker-blue",
+];
+const HSL_INPUTS: [&str; 3] = [
+    "color-picker-hue-degrees",
+    "color-picker-saturation",
+    "color-picker-lightness",
+];
+const MARKER_RADIUS: f64 = 5.0;
+
+/// Hue, saturation and value the picker shows. Kept apart from the color so that the hue of grays
+/// and the saturation of black survive edits.
+type Hsv = Rc<Cell<(f64, f64, f64)>>;
+
+/// Input that changed the color, left alone when the picker shows the new color.
+#[derive(Clone, Copy, PartialEq)]
+enum Source {
+    Rgb,
+    Hsl,
+    Hex,
+    Hue,
+    Alpha,
+    Square,
+    External,
+}
+
+/// Color the picker was last opened for, along with the hue, saturation and value it shows.
+pub struct ColorPicker {
+    color: Rc<RefCell<Color>>,
+    hsv: Hsv,
+}
 
 pub fn init(dom: Rc<RefCell<Dom>>, color: Rc<RefCell<Color>>) {
     dom.borrow()
-        .tool_bar
-        .color
+        .document
+        .get_element_by_id("color")
+        .unwrap()
         .class_list()
         .add_1("selected")
         .unwrap();
     show_color_picker(Rc::clone(&dom));
-    let color_picker_color = dom
-        .borrow()
-        .document
-        .get_element_by_id("color-picker-color")
-        .unwrap()
-        .dyn_into::<HtmlElement>()
-        .unwrap();
-    let color_picker_color = Rc::new(color_picker_color);
-    let color_inputs = dom
-        .borrow()
-        .document
-        .get_elements_by_class_name("color-picker-color");
-    let color_inputs = Rc::new(color_inputs);
-    for color_input_index in 0..color_inputs.length() {
-        let color_input = color_inputs
-            .item(color_input_index)
-            .unwrap()
-            .dyn_into::<HtmlElement>()
-            .unwrap();
-        let on_input = on_input(
-            Rc::clone(&color_picker_color),
-            Rc::clone(&color_inputs),
-            Rc::clone(&color),
-        );
-        color_input.set_oninput(Some(on_input.as_ref().unchecked_ref()));
+    let hsv = Rc::new(Cell::new(color.borrow().to_hsv()));
+    for id in RGB_INPUTS {
+        let on_input = on_rgb_input(Rc::clone(&dom), Rc::clone(&color), Rc::clone(&hsv));
+        get_input(&dom.borrow(), id).set_oninput(Some(on_input.as_ref().unchecked_ref()));
         on_input.forget();
     }
-    read_color(&*color_picker_color, &*color_inputs, &*color.borrow_mut());
+    for id in HSL_INPUTS {
+        let on_input = on_hsl_input(Rc::clone(&dom), Rc::clone(&color), Rc::clone(&hsv));
+        get_input(&dom.borrow(), id).set_oninput(Some(on_input.as_ref().unchecked_ref()));
+        on_input.forget();
+    }
+    let on_input = on_hex_input(Rc::clone(&dom), Rc::clone(&color), Rc::clone(&hsv));
+    get_input(&dom.borrow(), "color-picker-hex")
+        .set_oninput(Some(on_input.as_ref().unchecked_ref()));
+    on_input.forget();
+    let on_input = on_hue_input(Rc::clone(&dom), Rc::clone(&color), Rc::clone(&hsv));
+    get_input(&dom.borrow(), "color-picker-hue")
+        .set_oninput(Some(on_input.as_ref().unchecked_ref()));
+    on_input.forget();
+    let on_input = on_alpha_input(Rc::clone(&dom), Rc::clone(&color), Rc::clone(&hsv));
+    get_input(&dom.borrow(), "color-picker-alpha")
+        .set_oninput(Some(on_input.as_ref().unchecked_ref()));
+    on_input.forget();
+    let on_pointer = on_square_pointer(Rc::clone(&dom), Rc::clone(&color), Rc::clone(&hsv));
+    let square = get_square(&dom.borrow());
+    square.set_onpointerdown(Some(on_pointer.as_ref().unchecked_ref()));
+    square.set_onpointermove(Some(on_pointer.as_ref().unchecked_ref()));
+    on_pointer.forget();
+    show(&dom.borrow(), &color.borrow(), hsv.get(), Source::External);
+    dom.borrow_mut().color_picker = Some(ColorPicker { color, hsv });
+}
+
+/// Shows the color of the picker again after something other than the picker has changed it.
+pub fn refresh(dom: &Dom) {
+    if let Some(ColorPicker { color, hsv }) = &dom.color_picker {
+        let color = color.borrow();
+        hsv.set(reconcile(&color, hsv.get()));
+        show(dom, &color, hsv.get(), Source::External);
+    }
 }
 
-fn on_input(
-    color_picker_color: Rc<HtmlElement>,
-    color_inputs: Rc<HtmlCollection>,
+fn on_rgb_input(
+    dom: Rc<RefCell<Dom>>,
     color: Rc<RefCell<Color>>,
-) -> Closure<dyn FnMut(InputEvent)> {
-    Closure::wrap(Box::new(move |event: InputEvent| {
-        let input = event
-            .current_target()
-            .unwrap()
-            .dyn_into::<HtmlInputElement>()
-            .unwrap();
-        let mut digits: LinkedList<u32> = input
-            .value()
-            .chars()
-            .filter_map(|char| char.to_digit(10))
+    hsv: Hsv,
+) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        let dom = dom.borrow();
+        let channels = RGB_INPUTS.map(|id| {
+            let input = get_input(&dom, id);
+            let channel = input.value().trim().parse::<u8>().ok();
+            mark_invalid(&input, channel.is_none());
+            channel
+        });
+        if let [Some(r), Some(g), Some(b)] = channels {
+            let mut color = color.borrow_mut();
+            *color = Color::new(r, g, b, color.a);
+            hsv.set(reconcile(&color, hsv.get()));
+            show(&dom, &color, hsv.get(), Source::Rgb);
+            drop(color);
+            palette::show(&dom);
+        }
+    })
+}
+
+fn on_hsl_input(
+    dom: Rc<RefCell<Dom>>,
+    color: Rc<RefCell<Color>>,
+    hsv: Hsv,
+) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        let dom = dom.borrow();
+        let maxima = [360.0, 100.0, 100.0];
+        let values = HSL_INPUTS.map(|id| get_input(&dom, id));
+        let values: Vec<Option<f64>> = values
+            .iter()
+            .zip(maxima)
+            .map(|(input, max)| {
+                let value = input
+                    .value()
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|value| (0.0..=max).contains(value));
+                mark_invalid(input, value.is_none());
+                value
+            })
             .collect();
-        loop {
-            let digit = digits.pop_front();
-            match digit {
-                Some(digit) => {
-                    if digit == 0 {
-                        continue;
-                    } else {
-                        digits.push_front(digit);
-                    }
-                }
-                None => digits.push_front(0),
-            }
-            break;
+        if let [Some(hue), Some(saturation), Some(lightness)] = values[..] {
+            let mut color = color.borrow_mut();
+            *color = Color::from_hsl(hue, saturation / 100.0, lightness / 100.0, color.a);
+            let (_, saturation, value) = reconcile(&color, hsv.get());
+            hsv.set((hue, saturation, value));
+            show(&dom, &color, hsv.get(), Source::Hsl);
+            drop(color);
+            palette::show(&dom);
         }
-        let mut value = digits.iter().fold(0, |acc, digit| acc * 10 + digit);
-        if value > 255 {
-            value = 255;
+    })
+}
+
+fn on_hex_input(
+    dom: Rc<RefCell<Dom>>,
+    color: Rc<RefCell<Color>>,
+    hsv: Hsv,
+) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        let dom = dom.borrow();
+        let input = get_input(&dom, "color-picker-hex");
+        let parsed = Color::from_hex(&input.value());
+        mark_invalid(&input, parsed.is_none());
+        if let Some(parsed) = parsed {
+            let mut color = color.borrow_mut();
+            *color = Color::new(parsed.r, parsed.g, parsed.b, color.a);
+            hsv.set(reconcile(&color, hsv.get()));
+            show(&dom, &color, hsv.get(), Source::Hex);
+            drop(color);
+            palette::show(&dom);
         }
-        input.set_value(&*value.to_string());
-        write_color(&color_inputs, &color_picker_color, &mut *color.borrow_mut());
-    }) as Box<dyn FnMut(InputEvent)>)
+    })
 }
 
-fn read_color(color_picker_color: &HtmlElement, color_inputs: &HtmlCollection, color: &Color) {
-    color_picker_color
+fn on_hue_input(
+    dom: Rc<RefCell<Dom>>,
+    color: Rc<RefCell<Color>>,
+    hsv: Hsv,
+) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        let dom = dom.borrow();
+        let hue = get_input(&dom, "color-picker-hue")
+            .value()
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        let mut color = color.borrow_mut();
+        let (_, saturation, value) = reconcile(&color, hsv.get());
+        *color = Color::from_hsv(hue, saturation, value, color.a);
+        hsv.set((hue, saturation, value));
+        show(&dom, &color, hsv.get(), Source::Hue);
+        drop(color);
+        palette::show(&dom);
+    })
+}
+
+fn on_alpha_input(
+    dom: Rc<RefCell<Dom>>,
+    color: Rc<RefCell<Color>>,
+    hsv: Hsv,
+) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        let dom = dom.borrow();
+        let alpha = get_input(&dom, "color-picker-alpha")
+            .value()
+            .parse::<u8>()
+            .unwrap_or(255);
+        let mut color = color.borrow_mut();
+        color.a = alpha;
+        show(&dom, &color, hsv.get(), Source::Alpha);
+        drop(color);
+        palette::show(&dom);
+    })
+}
+
+/// Picks saturation and value from the square while the primary button is held. The square keeps
+/// the pointer once pressed, so that dragging past its edges picks from the edges.
+fn on_square_pointer(
+    dom: Rc<RefCell<Dom>>,
+    color: Rc<RefCell<Color>>,
+    hsv: Hsv,
+) -> Closure<dyn FnMut(PointerEvent)> {
+    Closure::wrap(Box::new(move |pointer_event: PointerEvent| {
+        if pointer_event.buttons() & 1 == 0 {
+            return;
+        }
+        let dom = dom.borrow();
+        let square = get_square(&dom);
+        if pointer_event.type_() == "pointerdown" {
+            let _ = square.set_pointer_capture(pointer_event.pointer_id());
+        }
+        let max_x = (square.width() - 1) as f64;
+        let max_y = (square.height() - 1) as f64;
+        let saturation = (pointer_event.offset_x() as f64 / max_x).clamp(0.0, 1.0);
+        let value = 1.0 - (pointer_event.offset_y() as f64 / max_y).clamp(0.0, 1.0);
+        let mut color = color.borrow_mut();
+        let (hue, _, _) = reconcile(&color, hsv.get());
+        *color = Color::from_hsv(hue, saturation, value, color.a);
+        hsv.set((hue, saturation, value));
+        show(&dom, &color, hsv.get(), Source::Square);
+        drop(color);
+        palette::show(&dom);
+    }) as Box<dyn FnMut(PointerEvent)>)
+}
+
+/// HSV of the color, borrowing from `previous` what the color cannot tell: the hue of grays and
+/// the saturation of black.
+fn reconcile(color: &Color, previous: (f64, f64, f64)) -> (f64, f64, f64) {
+    let (hue, saturation, value) = color.to_hsv();
+    if value == 0.0 {
+        (previous.0, previous.1, value)
+    } else if saturation == 0.0 {
+        (previous.0, saturation, value)
+    } else {
+        (hue, saturation, value)
+    }
+}
+
+fn show(dom: &Dom, color: &Color, hsv: (f64, f64, f64), source: Source) {
+    get_element(dom, "color-picker-color")
         .style()
-        .set_property("background-color", &*color.as_css_value())
+        .set_property("background-color", &color.as_css_value())
         .unwrap();
-    for color_input_index in 0..color_inputs.length() {
-        let color_input = color_inputs
-            .item(color_input_index)
-            .unwrap()
-            .dyn_into::<HtmlInputElement>()
-            .unwrap();
-        let color_value: u8;
-        match color_input.id().as_str() {
-            "color-picker-red" => color_value = color.r,
-            "color-picker-green" => color_value = color.g,
-            "color-picker-blue" => color_value = color.b,
-            id => panic!("Unknown color input {}", id),
+    if source != Source::Rgb {
+        let channels = [color.r, color.g, color.b];
+        for (id, channel) in RGB_INPUTS.iter().zip(channels) {
+            show_value(dom, id, &channel.to_string());
+        }
+    }
+    if source != Source::Hsl {
+        let (_, saturation, lightness) = color.to_hsl();
+        let values = [hsv.0, saturation * 100.0, lightness * 100.0];
+        for (id, value) in HSL_INPUTS.iter().zip(values) {
+            show_value(dom, id, &value.round().to_string());
         }
-        color_input.set_value(&*color_value.to_string());
     }
+    if source != Source::Hex {
+        show_value(dom, "color-picker-hex", &color.to_hex());
+    }
+    if source != Source::Hue {
+        show_value(dom, "color-picker-hue", &hsv.0.round().to_string());
+    }
+    if source != Source::Alpha {
+        show_value(dom, "color-picker-alpha", &color.a.to_string());
+    }
+    get_element(dom, "color-picker-alpha-value")
+        .set_text_content(Some(&format!("{}%", (color.a as f64 / 2.55).round())));
+    render_square(dom, hsv);
+}
+
+fn show_value(dom: &Dom, id: &str, value: &str) {
+    let input = get_input(dom, id);
+    input.set_value(value);
+    mark_invalid(&input, false);
 }
 
-fn write_color(color_inputs: &HtmlCollection, color_picker_color: &HtmlElement, color: &mut Color) {
-    for color_input_index in 0..color_inputs.length() {
-        let color_input = color_inputs
-            .item(color_input_index)
-            .unwrap()
-            .dyn_into::<HtmlInputElement>()
-            .unwrap();
-        let color_value = color_input.value().parse::<u8>().unwrap_or(255);
-        match color_input.id().as_str() {
-            "color-picker-red" => color.r = color_value,
-            "color-picker-green" => color.g = color_value,
-            "color-picker-blue" => color.b = color_value,
-            id => panic!("Unknown color input {}", id),
+/// Paints every saturation, left to right, and value, top to bottom, of the hue, then circles the
+/// current one.
+fn render_square(dom: &Dom, hsv: (f64, f64, f64)) {
+    let square = get_square(dom);
+    let width = square.width();
+    let height = square.height();
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        let value = 1.0 - y as f64 / (height - 1) as f64;
+        for x in 0..width {
+            let saturation = x as f64 / (width - 1) as f64;
+            let color = Color::from_hsv(hsv.0, saturation, value, 255);
+            data.extend_from_slice(&[color.r, color.g, color.b, color.a]);
         }
     }
-    color_picker_color
-        .style()
-        .set_property("background-color", &*color.as_css_value())
+    let context = square
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()
         .unwrap();
+    let image_data =
+        ImageData::new_with_u8_clamped_array_and_sh(Clamped(&data), width, height).unwrap();
+    context.put_image_data(&image_data, 0.0, 0.0).unwrap();
+    let x = hsv.1 * (width - 1) as f64;
+    let y = (1.0 - hsv.2) * (height - 1) as f64;
+    context.set_line_width(2.0);
+    for (radius, style) in [(MARKER_RADIUS + 1.0, "black"), (MARKER_RADIUS, "white")] {
+        context.begin_path();
+        context.arc(x, y, radius, 0.0, 2.0 * PI).unwrap();
+        context.set_stroke_style_str(style);
+        context.stroke();
+    }
+}
+
+fn mark_invalid(input: &HtmlInputElement, is_invalid: bool) {
+    input
+        .class_list()
+        .toggle_with_force("invalid", is_invalid)
+        .unwrap();
+}
+
+fn get_square(dom: &Dom) -> HtmlCanvasElement {
+    dom.document
+        .get_element_by_id("color-picker-square")
+        .unwrap()
+        .dyn_into::<HtmlCanvasElement>()
+        .unwrap()
+}
+
+fn get_input(dom: &Dom, id: &str) -> HtmlInputElement {
+    dom.document
+        .get_element_by_id(id)
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+}
+
+fn get_element(dom: &Dom, id: &str) -> HtmlElement {
+    dom.document
+        .get_element_by_id(id)
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
 }
 
 fn show_color_picker(dom: Rc<RefCell<Dom>>) {
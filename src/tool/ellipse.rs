@@ -0,0 +1,143 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use web_sys::PointerEvent;
+
+use crate::brush::Brush;
+use crate::canvas::surface::Surface;
+use crate::dom::Dom;
+use crate::ellipse::Ellipse;
+use crate::palette::Palette;
+use crate::point::Point;
+use crate::rectangle::{self, Rectangle};
+use crate::tool::shape::{self, Style};
+use crate::tool::tool::Tool;
+
+pub struct EllipseTool {
+    palette: Palette,
+    brush: Rc<RefCell<Brush>>,
+    /// Corner the drag started from and the colors of its button.
+    drag: Option<(Point, Palette)>,
+}
+
+impl EllipseTool {
+    pub fn new(palette: Palette, brush: Rc<RefCell<Brush>>) -> EllipseTool {
+        EllipseTool {
+            palette,
+            brush,
+            drag: None,
+        }
+    }
+}
+
+impl Tool for EllipseTool {
+    fn id(&self) -> &'static str {
+        "ellipse"
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["brush-options", "ellipse-options"]
+    }
+
+    fn on_press(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        if !dom.canvas.is_active_layer_editable() {
+            return;
+        }
+        dom.history.begin(&dom.canvas);
+        let colors = self.palette.for_button(pointer_event.button());
+        let point_a = dom.canvas.get_point(pointer_event);
+        self.drag = Some((point_a, colors));
+    }
+
+    fn on_move(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let (point_a, colors) = match &self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        let ellipse = get_ellipse(dom, point_a, pointer_event);
+        let style = read_style(dom, colors, &self.brush.borrow());
+        let bounds = ellipse.bounds.expand(style.brush.reach());
+        dom.canvas.render_preview(&bounds, |pixels, origin| {
+            let ellipse = Ellipse::new(ellipse.bounds.translate(-origin.x, -origin.y));
+            put(pixels, &ellipse, &style)
+        });
+    }
+
+    fn on_release(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let (point_a, colors) = match self.drag.take() {
+            Some(drag) => drag,
+            None => return,
+        };
+        let ellipse = get_ellipse(dom, &point_a, pointer_event);
+        let style = read_style(dom, &colors, &self.brush.borrow());
+        let area = put(dom.canvas.pixels_mut(), &ellipse, &style);
+        dom.canvas.discard_preview();
+        if let Some(area) = area {
+            dom.canvas.invalidate(&area);
+        }
+        dom.history.commit(&dom.canvas);
+    }
+
+    fn on_cancel(&mut self, dom: &mut Dom) {
+        if self.drag.take().is_some() {
+            dom.canvas.discard_preview();
+            dom.history.rollback(&mut dom.canvas);
+        }
+    }
+}
+
+/// Paints the ellipse, filling its interior first so that the outline stays on top. Like segments,
+/// the outline is clipped to the canvas enlarged by the reach of the brush. Returns the area that
+/// may have changed.
+pub fn put(pixels: &mut Surface, ellipse: &Ellipse, style: &Style) -> Option<Rectangle> {
+    let width = pixels.width() as i32;
+    let height = pixels.height() as i32;
+    let mut bounds = None;
+    if style.mode.has_fill() {
+        for (y, from_x, to_x) in ellipse.spans() {
+            if y < 0 || y >= height {
+                continue;
+            }
+            for x in from_x.max(0)..=to_x.min(width - 1) {
+                pixels.blend(&Point::new(x, y), &style.fill);
+            }
+        }
+        bounds = ellipse.bounds.clip(&pixels.width(), &pixels.height());
+    }
+    if style.mode.has_outline() {
+        let reach = style.brush.reach();
+        let points: Vec<Point> = ellipse
+            .points()
+            .into_iter()
+            .filter(|point| {
+                point.x >= -reach
+                    && point.x < width + reach
+                    && point.y >= -reach
+                    && point.y < height + reach
+            })
+            .collect();
+        let outline_bounds = style.brush.put_points(pixels, &points, &style.outline);
+        bounds = rectangle::union(bounds, outline_bounds);
+    }
+    bounds
+}
+
+/// Shift constrains the ellipse to a circle, Alt draws it from the center outwards.
+fn get_ellipse(dom: &Dom, point_a: &Point, pointer_event: &PointerEvent) -> Ellipse {
+    let point_b = dom.canvas.get_point(pointer_event);
+    Ellipse::new(Rectangle::from_drag(
+        point_a,
+        &point_b,
+        pointer_event.shift_key(),
+        pointer_event.alt_key(),
+    ))
+}
+
+fn read_style(dom: &Dom, colors: &Palette, brush: &Brush) -> Style {
+    let mode = shape::read_mode(dom, "ellipse-mode");
+    Style::new(
+        mode,
+        brush.clone(),
+        &colors.primary.borrow(),
+        &colors.secondary.borrow(),
+    )
+}
@@ -0,0 +1,66 @@
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+
+use crate::brush::{self, Brush, Tip};
+use crate::canvas::surface::Surface;
+use crate::color::Color;
+use crate::dom::Dom;
+use crate::rectangle::Rectangle;
+use crate::segment::Segment;
+use crate::tool::pencil::{Contact, Freehand, Stroke};
+
+/// Replaces pixels outright, so it never needs the layer as it was when the stroke began.
+struct EraserStroke;
+
+impl Stroke for EraserStroke {
+    fn paint(
+        &self,
+        dom: &mut Dom,
+        _: Option<&Surface>,
+        segment: &Segment,
+        _: Contact,
+    ) -> Option<Rectangle> {
+        let (brush, color) = read_options(dom);
+        brush.replace(dom.canvas.pixels_mut(), segment, &color)
+    }
+}
+
+pub fn new() -> Freehand {
+    Freehand::new("eraser", &["eraser-options"], EraserStroke)
+}
+
+/// Erasing to the background paints the secondary color, which the canvas also grows with.
+fn read_options(dom: &Dom) -> (Brush, Color) {
+    let size = dom
+        .document
+        .get_element_by_id("eraser-size")
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+        .value()
+        .parse::<u32>()
+        .unwrap_or(brush::MIN_SIZE);
+    let footprint = dom
+        .document
+        .get_element_by_id("eraser-footprint")
+        .unwrap()
+        .dyn_into::<HtmlSelectElement>()
+        .unwrap()
+        .value();
+    let tip = match footprint.as_str() {
+        "round" => Tip::Round,
+        _ => Tip::Square,
+    };
+    let target = dom
+        .document
+        .get_element_by_id("eraser-target")
+        .unwrap()
+        .dyn_into::<HtmlSelectElement>()
+        .unwrap()
+        .value();
+    let color = match target.as_str() {
+        "background" => dom.palette.secondary.borrow().clone(),
+        _ => Color::transparent(),
+    };
+    (Brush::new(size, tip), color)
+}
@@ -0,0 +1,126 @@
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, PointerEvent};
+
+use crate::dom::Dom;
+use crate::palette::{self, Palette};
+use crate::point::Point;
+use crate::rectangle::Rectangle;
+use crate::tool::color_picker;
+use crate::tool::tool::Tool;
+
+/// Number of canvas pixels shown on each side of the sampled one in the preview.
+const PREVIEW_RADIUS: i32 = 4;
+/// Size, in preview pixels, of a single magnified canvas pixel.
+const PREVIEW_ZOOM: i32 = 10;
+/// Distance between the cursor and the preview.
+const PREVIEW_OFFSET: i32 = 16;
+
+pub struct EyedropperTool {
+    palette: Palette,
+}
+
+impl EyedropperTool {
+    pub fn new(palette: Palette) -> EyedropperTool {
+        EyedropperTool { palette }
+    }
+}
+
+impl Tool for EyedropperTool {
+    fn id(&self) -> &'static str {
+        "eyedropper"
+    }
+
+    /// Samples the color shown on the canvas, whichever layers it comes from, into the color of
+    /// the button that clicks.
+    fn on_press(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let point = dom.canvas.get_point(pointer_event);
+        if !dom.canvas.is_point_on_canvas(&point) {
+            return;
+        }
+        let sample = dom.canvas.composite(&Rectangle::new(point, point));
+        *self.palette.pick(pointer_event.button()).borrow_mut() = sample.get(&Point::new(0, 0));
+        palette::show(dom);
+        color_picker::refresh(dom);
+    }
+
+    /// Keeps the magnified preview next to the cursor while it hovers the canvas.
+    fn on_move(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let preview = preview(dom);
+        let point = dom.canvas.get_point(pointer_event);
+        if !dom.canvas.is_point_on_canvas(&point) {
+            preview.class_list().add_1("hidden").unwrap();
+            return;
+        }
+        render_preview(dom, &preview, &point);
+        let style = preview.style();
+        style
+            .set_property(
+                "left",
+                &format!("{}px", pointer_event.client_x() + PREVIEW_OFFSET),
+            )
+            .unwrap();
+        style
+            .set_property(
+                "top",
+                &format!("{}px", pointer_event.client_y() + PREVIEW_OFFSET),
+            )
+            .unwrap();
+        preview.class_list().remove_1("hidden").unwrap();
+    }
+
+    fn on_leave(&mut self, dom: &mut Dom) {
+        preview(dom).class_list().add_1("hidden").unwrap();
+    }
+
+    fn on_deactivate(&mut self, dom: &mut Dom) {
+        preview(dom).class_list().add_1("hidden").unwrap();
+    }
+}
+
+fn preview(dom: &Dom) -> HtmlCanvasElement {
+    dom.document
+        .get_element_by_id("eyedropper-preview")
+        .unwrap()
+        .dyn_into::<HtmlCanvasElement>()
+        .unwrap()
+}
+
+fn render_preview(dom: &Dom, preview: &HtmlCanvasElement, point: &Point) {
+    let context = preview
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()
+        .unwrap();
+    let size = (PREVIEW_RADIUS * 2 + 1) * PREVIEW_ZOOM;
+    let area = Rectangle::new(
+        Point::new(point.x - PREVIEW_RADIUS, point.y - PREVIEW_RADIUS),
+        Point::new(point.x + PREVIEW_RADIUS, point.y + PREVIEW_RADIUS),
+    )
+    .clip(&dom.canvas.element.width(), &dom.canvas.element.height())
+    .unwrap();
+    let composite = dom.canvas.composite(&area);
+    preview.set_width(size as u32);
+    preview.set_height(size as u32);
+    context.set_fill_style_str("grey");
+    context.fill_rect(0_f64, 0_f64, size as f64, size as f64);
+    for dy in -PREVIEW_RADIUS..=PREVIEW_RADIUS {
+        for dx in -PREVIEW_RADIUS..=PREVIEW_RADIUS {
+            let source = Point::new(point.x + dx, point.y + dy);
+            if !dom.canvas.is_point_on_canvas(&source) {
+                continue;
+            }
+            let x = ((dx + PREVIEW_RADIUS) * PREVIEW_ZOOM) as f64;
+            let y = ((dy + PREVIEW_RADIUS) * PREVIEW_ZOOM) as f64;
+            let zoom = PREVIEW_ZOOM as f64;
+            context.set_fill_style_str("white");
+            context.fill_rect(x, y, zoom, zoom);
+            let pixel = composite.get(&Point::new(source.x - area.from.x, source.y - area.from.y));
+            context.set_fill_style_str(&pixel.as_css_value());
+            context.fill_rect(x, y, zoom, zoom);
+        }
+    }
+    let center = (PREVIEW_RADIUS * PREVIEW_ZOOM) as f64;
+    context.set_stroke_style_str("black");
+    context.stroke_rect(center, center, PREVIEW_ZOOM as f64, PREVIEW_ZOOM as f64);
+}
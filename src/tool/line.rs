@@ -1,125 +1,110 @@
 use std::cell::RefCell;
 use std::rc::Rc;
-use wasm_bindgen::closure::Closure;
-use web_sys::MouseEvent;
+use web_sys::PointerEvent;
 
+use crate::brush::Brush;
 use crate::color::Color;
 use crate::dom::Dom;
+use crate::palette::Palette;
 use crate::point::Point;
+use crate::rectangle::Rectangle;
 use crate::segment::Segment;
-use crate::util::flat_idx;
+use crate::tool::tool::Tool;
 
-pub fn init(dom: Rc<RefCell<Dom>>, color: Rc<RefCell<Color>>) {
-    let tools = dom.borrow().document.get_elements_by_class_name("tool");
-    for tool_idx in 0..tools.length() {
-        let tool = tools.item(tool_idx).unwrap();
-        tool.class_list().remove_1("selected").unwrap();
-    }
-    dom.borrow()
-        .tool_bar
-        .line
-        .class_list()
-        .add_1("selected")
-        .unwrap();
-    let start = start(Rc::clone(&dom), Rc::clone(&color));
-    dom.borrow_mut().canvas.set_on_mouse_down(Some(&start));
-    start.forget();
+pub struct LineTool {
+    palette: Palette,
+    brush: Rc<RefCell<Brush>>,
+    /// Start of the line being dragged and its color.
+    drag: Option<(Point, Rc<RefCell<Color>>)>,
 }
 
-pub fn put(pixels: &mut Vec<Rc<Color>>, width: &u32, segment: &Segment, color: &Color) {
-    let point_a = segment.a;
-    let point_b = segment.b;
-    let kx = if point_a.x <= point_b.x { 1 } else { -1 };
-    let ky = if point_a.y <= point_b.y { 1 } else { -1 };
-    let dx = (point_a.x - point_b.x).abs();
-    let dy = -(point_a.y - point_b.y).abs();
-    let mut e = dx + dy;
-    let mut e2: i32;
-    let mut point = point_a.clone();
-    loop {
-        pixels[flat_idx(&point, width)] = Rc::new(color.clone());
-        if point.x == point_b.x && point.y == point_b.y {
-            break;
-        }
-        e2 = 2 * e;
-        if e2 >= dy {
-            e += dy;
-            point.x += kx;
-        }
-        if e2 <= dx {
-            e += dx;
-            point.y += ky;
+impl LineTool {
+    pub fn new(palette: Palette, brush: Rc<RefCell<Brush>>) -> LineTool {
+        LineTool {
+            palette,
+            brush,
+            drag: None,
         }
     }
-}
 
-fn start(dom: Rc<RefCell<Dom>>, color: Rc<RefCell<Color>>) -> Closure<dyn FnMut(MouseEvent)> {
-    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
-        dom.borrow_mut().canvas.set_on_mouse_down(None);
-        let point_a = dom.borrow().canvas.get_point(&mouse_event);
-        let point_a = Rc::new(point_a);
-        let advance = advance(Rc::clone(&dom), Rc::clone(&point_a), Rc::clone(&color));
-        dom.borrow_mut().canvas.set_on_mouse_move(Some(&advance));
-        advance.forget();
-        let end = end(Rc::clone(&dom), Rc::clone(&point_a), Rc::clone(&color));
-        dom.borrow_mut().canvas.set_on_mouse_up(Some(&end));
-        dom.borrow_mut().canvas.set_on_mouse_leave(Some(&end));
-        end.forget();
-    }) as Box<dyn FnMut(MouseEvent)>)
+    /// Segment from the start of the drag to the cursor, clipped to the reach of the brush.
+    fn get_segment(&self, dom: &Dom, pointer_event: &PointerEvent) -> Option<Segment> {
+        let (point_a, _) = self.drag.as_ref()?;
+        let point_b = get_end(dom, point_a, pointer_event);
+        let reach = self.brush.borrow().reach();
+        dom.canvas.get_segment(point_a, &point_b, reach)
+    }
 }
 
-fn advance(
-    dom: Rc<RefCell<Dom>>,
-    point_a: Rc<Point>,
-    color: Rc<RefCell<Color>>,
-) -> Closure<dyn FnMut(MouseEvent)> {
-    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
-        let point_b = dom.borrow().canvas.get_point(&mouse_event);
-        let segment = dom.borrow().canvas.get_segment(&point_a, &point_b);
-        if let Some(segment) = segment {
-            let mut pixels: Vec<Rc<Color>> = dom
+impl Tool for LineTool {
+    fn id(&self) -> &'static str {
+        "line"
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["brush-options"]
+    }
+
+    fn on_press(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        if !dom.canvas.is_active_layer_editable() {
+            return;
+        }
+        dom.history.begin(&dom.canvas);
+        let color = self.palette.pick(pointer_event.button());
+        let point_a = dom.canvas.get_point(pointer_event);
+        self.drag = Some((point_a, color));
+    }
+
+    fn on_move(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let color = match &self.drag {
+            Some((_, color)) => color,
+            None => return,
+        };
+        let segment = match self.get_segment(dom, pointer_event) {
+            Some(segment) => segment,
+            None => return dom.canvas.discard_preview(),
+        };
+        let brush = self.brush.borrow();
+        let bounds = Rectangle::new(segment.a, segment.b).expand(brush.reach());
+        dom.canvas.render_preview(&bounds, |pixels, origin| {
+            let segment = segment.translate(-origin.x, -origin.y);
+            brush.put(pixels, &segment, &color.borrow())
+        });
+    }
+
+    fn on_release(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let segment = self.get_segment(dom, pointer_event);
+        let color = match self.drag.take() {
+            Some((_, color)) => color,
+            None => return,
+        };
+        let area = segment.and_then(|segment| {
+            self.brush
                 .borrow()
-                .canvas
-                .pixels
-                .iter()
-                .map(|color| Rc::clone(color))
-                .collect();
-            put(
-                &mut pixels,
-                &dom.borrow().canvas.element.width(),
-                &segment,
-                &color.borrow(),
-            );
-            dom.borrow().canvas.render_external_pixels(&pixels);
-        } else {
-            dom.borrow().canvas.refresh();
+                .put(dom.canvas.pixels_mut(), &segment, &color.borrow())
+        });
+        dom.canvas.discard_preview();
+        if let Some(area) = area {
+            dom.canvas.invalidate(&area);
         }
-    }) as Box<dyn FnMut(MouseEvent)>)
-}
+        dom.history.commit(&dom.canvas);
+    }
 
-fn end(
-    dom: Rc<RefCell<Dom>>,
-    point_a: Rc<Point>,
-    color: Rc<RefCell<Color>>,
-) -> Closure<dyn FnMut(MouseEvent)> {
-    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
-        dom.borrow_mut().canvas.set_on_mouse_move(None);
-        dom.borrow_mut().canvas.set_on_mouse_up(None);
-        dom.borrow_mut().canvas.set_on_mouse_leave(None);
-        let point_b = dom.borrow().canvas.get_point(&mouse_event);
-        let segment = dom.borrow().canvas.get_segment(&point_a, &point_b);
-        if let Some(segment) = segment {
-            let width = dom.borrow().canvas.element.width();
-            put(
-                &mut dom.borrow_mut().canvas.pixels,
-                &width,
-                &segment,
-                &color.borrow(),
-            );
-            dom.borrow().canvas.refresh();
+    fn on_cancel(&mut self, dom: &mut Dom) {
+        if self.drag.take().is_some() {
+            dom.canvas.discard_preview();
+            dom.history.rollback(&mut dom.canvas);
         }
-        let start = start(Rc::clone(&dom), Rc::clone(&color));
-        dom.borrow_mut().canvas.set_on_mouse_down(Some(&start));
-        start.forget();
-    }) as Box<dyn FnMut(MouseEvent)>)
+    }
+}
+
+/// Shift snaps the line to multiples of 45°, or of 15° along with Alt.
+fn get_end(dom: &Dom, point_a: &Point, pointer_event: &PointerEvent) -> Point {
+    let point_b = dom.canvas.get_point(pointer_event);
+    let step = match (pointer_event.shift_key(), pointer_event.alt_key()) {
+        (true, true) => Some(15.0),
+        (true, false) => Some(45.0),
+        _ => None,
+    };
+    Segment::from_drag(point_a, &point_b, step).b
 }
@@ -0,0 +1,107 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use web_sys::HtmlElement;
+
+use crate::dom::Dom;
+use crate::tool::tool::Tool;
+
+/// Owns the tools and knows which one is active, so that the canvas events always reach it.
+pub struct ToolManager {
+    tools: Vec<Rc<RefCell<dyn Tool>>>,
+    active: Option<Rc<RefCell<dyn Tool>>>,
+}
+
+impl ToolManager {
+    pub fn new() -> ToolManager {
+        ToolManager {
+            tools: vec![],
+            active: None,
+        }
+    }
+
+    pub fn register(&mut self, tool: impl Tool + 'static) {
+        self.tools.push(Rc::new(RefCell::new(tool)));
+    }
+
+    fn find(&self, id: &str) -> Option<Rc<RefCell<dyn Tool>>> {
+        self.tools
+            .iter()
+            .find(|tool| tool.borrow().id() == id)
+            .map(Rc::clone)
+    }
+
+    /// CSS cursor of the active tool over the canvas.
+    pub fn cursor(&self) -> &'static str {
+        self.active
+            .as_ref()
+            .map_or("", |tool| tool.borrow().cursor())
+    }
+
+    pub fn is_active(&self, id: &str) -> bool {
+        self.active
+            .as_ref()
+            .is_some_and(|tool| tool.borrow().id() == id)
+    }
+}
+
+/// Makes the tool with the id the active one, cancelling whatever the previous one was doing.
+/// Selecting the active tool again leaves it as it is.
+pub fn select(dom: &mut Dom, id: &str) {
+    if dom.tools.is_active(id) {
+        return;
+    }
+    let tool = match dom.tools.find(id) {
+        Some(tool) => tool,
+        None => return,
+    };
+    if let Some(previous) = dom.tools.active.take() {
+        let mut previous = previous.borrow_mut();
+        previous.on_cancel(dom);
+        previous.on_deactivate(dom);
+    }
+    dom.tools.active = Some(Rc::clone(&tool));
+    let mut tool = tool.borrow_mut();
+    show(dom, &*tool);
+    tool.on_activate(dom);
+}
+
+/// Passes an event to the active tool. The dom is borrowed for as long as the tool handles it.
+pub fn dispatch(dom: &RefCell<Dom>, handle: impl FnOnce(&mut dyn Tool, &mut Dom)) {
+    let tool = match &dom.borrow().tools.active {
+        Some(tool) => Rc::clone(tool),
+        None => return,
+    };
+    handle(&mut *tool.borrow_mut(), &mut dom.borrow_mut());
+}
+
+/// Marks the tool as selected in the tool bar, shows its options and cursor, and hides the other
+/// options.
+fn show(dom: &Dom, tool: &dyn Tool) {
+    let tools = dom.document.get_elements_by_class_name("tool");
+    for tool_idx in 0..tools.length() {
+        let element = tools.item(tool_idx).unwrap();
+        element
+            .class_list()
+            .toggle_with_force("selected", element.id() == tool.id())
+            .unwrap();
+    }
+    let tool_options = dom.document.get_elements_by_class_name("tool-options");
+    for tool_options_idx in 0..tool_options.length() {
+        let options = tool_options.item(tool_options_idx).unwrap();
+        let is_for_tool = tool.options().contains(&options.id().as_str());
+        options
+            .class_list()
+            .toggle_with_force("hidden", !is_for_tool)
+            .unwrap();
+    }
+    dom.document
+        .get_element_by_id("viewport")
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
+        .style()
+        .set_property("cursor", tool.cursor())
+        .unwrap();
+}
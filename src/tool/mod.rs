@@ -1,4 +1,16 @@
+pub mod brush_options;
+pub mod bucket;
 pub mod color_picker;
+pub mod ellipse;
+pub mod eraser;
+pub mod eyedropper;
 pub mod line;
+pub mod manager;
 pub mod pencil;
+pub mod rectangle;
+pub mod select;
+pub mod shape;
+#[allow(clippy::module_inception)]
+pub mod tool;
 pub mod tool_bar;
+pub mod transform;
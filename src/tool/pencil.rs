@@ -1,75 +1,199 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use wasm_bindgen::closure::Closure;
-use web_sys::MouseEvent;
+use web_sys::PointerEvent;
 
-use crate::color::Color;
+use crate::brush::{self, Brush};
+use crate::canvas::surface::Surface;
 use crate::dom::Dom;
+use crate::palette::Palette;
 use crate::point::Point;
-use crate::tool;
+use crate::rectangle::{self, Rectangle};
+use crate::segment::Segment;
+use crate::tool::tool::Tool;
 
-pub fn init(dom: Rc<RefCell<Dom>>, color: Rc<RefCell<Color>>) {
-    let tools = dom.borrow().document.get_elements_by_class_name("tool");
-    for tool_idx in 0..tools.length() {
-        let tool = tools.item(tool_idx).unwrap();
-        tool.class_list().remove_1("selected").unwrap();
+/// How a freehand tool paints its strokes.
+pub trait Stroke {
+    /// Whether the stroke begun by the event composites its color over the active layer as it was
+    /// when the stroke began, which `paint` then gets as its base.
+    fn blends(&self, _dom: &Dom, _pointer_event: &PointerEvent) -> bool {
+        false
     }
-    dom.borrow()
-        .tool_bar
-        .pencil
-        .class_list()
-        .add_1("selected")
-        .unwrap();
-    let start = start(Rc::clone(&dom), Rc::clone(&color));
-    dom.borrow_mut().canvas.set_on_mouse_down(Some(&start));
-    start.forget();
+
+    /// Paints a single segment of the stroke and returns the area that may have changed.
+    fn paint(
+        &self,
+        dom: &mut Dom,
+        base: Option<&Surface>,
+        segment: &Segment,
+        contact: Contact,
+    ) -> Option<Rectangle>;
+}
+
+/// How the pointer touches the canvas along a segment.
+#[derive(Clone, Copy)]
+pub struct Contact {
+    /// Button that began the stroke.
+    pub button: i16,
+    /// How hard the pointer presses, from 0 to 1.
+    pub pressure: f64,
+}
+
+/// Tool painting a stroke along the path of the pointer, segment by segment.
+pub struct Freehand {
+    id: &'static str,
+    options: &'static [&'static str],
+    stroke: Box<dyn Stroke>,
+    drag: Option<Drag>,
 }
 
-fn start(dom: Rc<RefCell<Dom>>, color: Rc<RefCell<Color>>) -> Closure<dyn FnMut(MouseEvent)> {
-    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
-        dom.borrow_mut().canvas.set_on_mouse_down(None);
-        let point_a = dom.borrow().canvas.get_point(&mouse_event);
-        let point_a = Rc::new(RefCell::new(point_a));
-        let advance = advance(Rc::clone(&dom), Rc::clone(&point_a), Rc::clone(&color));
-        dom.borrow_mut().canvas.set_on_mouse_move(Some(&advance));
-        advance.forget();
-        let end = end(Rc::clone(&dom), Rc::clone(&color));
-        dom.borrow_mut().canvas.set_on_mouse_up(Some(&end));
-        dom.borrow_mut().canvas.set_on_mouse_leave(Some(&end));
-        end.forget();
-    }) as Box<dyn FnMut(MouseEvent)>)
+/// Stroke in progress.
+struct Drag {
+    base: Option<Surface>,
+    point_a: Point,
+    button: i16,
+    /// Area that the stroke may have changed so far.
+    area: Option<Rectangle>,
 }
 
-fn advance(
-    dom: Rc<RefCell<Dom>>,
-    point_a: Rc<RefCell<Point>>,
-    color: Rc<RefCell<Color>>,
-) -> Closure<dyn FnMut(MouseEvent)> {
-    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
-        let point_b = dom.borrow().canvas.get_point(&mouse_event);
-        let segment = dom.borrow().canvas.get_segment(&point_a.borrow(), &point_b);
+impl Drag {
+    fn paint(&mut self, dom: &mut Dom, stroke: &dyn Stroke, segment: &Segment, contact: Contact) {
+        if let Some(area) = stroke.paint(dom, self.base.as_ref(), segment, contact) {
+            dom.canvas.invalidate(&area);
+            self.area = rectangle::union(self.area, Some(area));
+        }
+    }
+}
+
+impl Freehand {
+    pub fn new(
+        id: &'static str,
+        options: &'static [&'static str],
+        stroke: impl Stroke + 'static,
+    ) -> Freehand {
+        Freehand {
+            id,
+            options,
+            stroke: Box::new(stroke),
+            drag: None,
+        }
+    }
+}
+
+impl Tool for Freehand {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        self.options
+    }
+
+    fn on_press(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        if !dom.canvas.is_active_layer_editable() {
+            return;
+        }
+        dom.history.begin(&dom.canvas);
+        let base = match self.stroke.blends(dom, pointer_event) {
+            true => Some(dom.canvas.pixels().clone()),
+            false => None,
+        };
+        let button = pointer_event.button();
+        let point_a = dom.canvas.get_point(pointer_event);
+        let mut drag = Drag {
+            base,
+            point_a,
+            button,
+            area: None,
+        };
+        if dom
+            .canvas
+            .is_point_within_margin(&point_a, brush::MAX_REACH)
+        {
+            let contact = read_contact(pointer_event, button);
+            let segment = Segment::new(point_a, point_a);
+            drag.paint(dom, &*self.stroke, &segment, contact);
+        }
+        self.drag = Some(drag);
+    }
+
+    fn on_move(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let drag = match &mut self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        let point_b = dom.canvas.get_point(pointer_event);
+        let segment = dom
+            .canvas
+            .get_segment(&drag.point_a, &point_b, brush::MAX_REACH);
         if let Some(segment) = segment {
-            let width = dom.borrow().canvas.element.width();
-            tool::line::put(
-                &mut dom.borrow_mut().canvas.pixels,
-                &width,
-                &segment,
-                &color.borrow(),
-            );
-            dom.borrow().canvas.refresh();
+            let contact = read_contact(pointer_event, drag.button);
+            drag.paint(dom, &*self.stroke, &segment, contact);
+        }
+        drag.point_a = point_b;
+    }
+
+    fn on_release(&mut self, dom: &mut Dom, _: &PointerEvent) {
+        if let Some(drag) = self.drag.take() {
+            dom.history.commit_within(&dom.canvas, drag.area);
         }
-        *point_a.borrow_mut() = point_b;
-    }) as Box<dyn FnMut(MouseEvent)>)
+    }
+
+    /// Wipes the stroke off the layer.
+    fn on_cancel(&mut self, dom: &mut Dom) {
+        if self.drag.take().is_some() {
+            dom.history.rollback(&mut dom.canvas);
+        }
+    }
+}
+
+/// Paints with the color of the button, thinner and fainter the lighter a pen presses.
+struct PencilStroke {
+    palette: Palette,
+    brush: Rc<RefCell<Brush>>,
+}
+
+impl Stroke for PencilStroke {
+    /// Translucent colors would build up where the segments overlap if they were composited over
+    /// the layer as painted so far.
+    fn blends(&self, _: &Dom, pointer_event: &PointerEvent) -> bool {
+        let color = self.palette.pick(pointer_event.button());
+        let is_opaque = color.borrow().a == 255;
+        !is_opaque || pointer_event.pointer_type() == "pen"
+    }
+
+    fn paint(
+        &self,
+        dom: &mut Dom,
+        base: Option<&Surface>,
+        segment: &Segment,
+        contact: Contact,
+    ) -> Option<Rectangle> {
+        let brush = self.brush.borrow();
+        let size = (brush.size as f64 * contact.pressure).round() as u32;
+        let brush = Brush::new(size, brush.tip.clone());
+        let mut color = self.palette.pick(contact.button).borrow().clone();
+        color.a = (color.a as f64 * contact.pressure).round() as u8;
+        match base {
+            Some(base) => brush.put_over(dom.canvas.pixels_mut(), base, segment, &color),
+            None => brush.put(dom.canvas.pixels_mut(), segment, &color),
+        }
+    }
+}
+
+pub fn new(palette: Palette, brush: Rc<RefCell<Brush>>) -> Freehand {
+    Freehand::new(
+        "pencil",
+        &["brush-options"],
+        PencilStroke { palette, brush },
+    )
 }
 
-fn end(dom: Rc<RefCell<Dom>>, color: Rc<RefCell<Color>>) -> Closure<dyn FnMut(MouseEvent)> {
-    Closure::wrap(Box::new(move |_: MouseEvent| {
-        dom.borrow_mut().canvas.set_on_mouse_move(None);
-        dom.borrow_mut().canvas.set_on_mouse_up(None);
-        dom.borrow_mut().canvas.set_on_mouse_leave(None);
-        let start = start(Rc::clone(&dom), Rc::clone(&color));
-        dom.borrow_mut().canvas.set_on_mouse_down(Some(&start));
-        start.forget();
-    }) as Box<dyn FnMut(MouseEvent)>)
+/// Mice and fingers report no useful pressure, so they press fully.
+fn read_contact(pointer_event: &PointerEvent, button: i16) -> Contact {
+    let pressure = match pointer_event.pointer_type().as_str() {
+        "pen" => pointer_event.pressure() as f64,
+        _ => 1.0,
+    };
+    Contact { button, pressure }
 }
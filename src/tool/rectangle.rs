@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use web_sys::PointerEvent;
+
+use crate::brush::Brush;
+use crate::canvas::surface::Surface;
+use crate::dom::Dom;
+use crate::palette::Palette;
+use crate::point::Point;
+use crate::rectangle::{self, Rectangle};
+use crate::tool::shape::{self, Style};
+use crate::tool::tool::Tool;
+
+pub struct RectangleTool {
+    palette: Palette,
+    brush: Rc<RefCell<Brush>>,
+    /// Corner the drag started from and the colors of its button.
+    drag: Option<(Point, Palette)>,
+}
+
+impl RectangleTool {
+    pub fn new(palette: Palette, brush: Rc<RefCell<Brush>>) -> RectangleTool {
+        RectangleTool {
+            palette,
+            brush,
+            drag: None,
+        }
+    }
+}
+
+impl Tool for RectangleTool {
+    fn id(&self) -> &'static str {
+        "rectangle"
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["brush-options", "rectangle-options"]
+    }
+
+    fn on_press(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        if !dom.canvas.is_active_layer_editable() {
+            return;
+        }
+        dom.history.begin(&dom.canvas);
+        let colors = self.palette.for_button(pointer_event.button());
+        let point_a = dom.canvas.get_point(pointer_event);
+        self.drag = Some((point_a, colors));
+    }
+
+    fn on_move(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let (point_a, colors) = match &self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        let rectangle = get_rectangle(dom, point_a, pointer_event);
+        let style = read_style(dom, colors, &self.brush.borrow());
+        let bounds = rectangle.expand(style.brush.reach());
+        dom.canvas.render_preview(&bounds, |pixels, origin| {
+            put(pixels, &rectangle.translate(-origin.x, -origin.y), &style)
+        });
+    }
+
+    fn on_release(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let (point_a, colors) = match self.drag.take() {
+            Some(drag) => drag,
+            None => return,
+        };
+        let rectangle = get_rectangle(dom, &point_a, pointer_event);
+        let style = read_style(dom, &colors, &self.brush.borrow());
+        let area = put(dom.canvas.pixels_mut(), &rectangle, &style);
+        dom.canvas.discard_preview();
+        if let Some(area) = area {
+            dom.canvas.invalidate(&area);
+        }
+        dom.history.commit(&dom.canvas);
+    }
+
+    fn on_cancel(&mut self, dom: &mut Dom) {
+        if self.drag.take().is_some() {
+            dom.canvas.discard_preview();
+            dom.history.rollback(&mut dom.canvas);
+        }
+    }
+}
+
+/// Paints the rectangle, filling its interior first so that the outline stays on top. Returns the
+/// area that may have changed.
+pub fn put(pixels: &mut Surface, rectangle: &Rectangle, style: &Style) -> Option<Rectangle> {
+    let mut bounds = None;
+    if style.mode.has_fill() {
+        bounds = rectangle.clip(&pixels.width(), &pixels.height());
+        if let Some(area) = bounds {
+            for y in area.from.y..=area.to.y {
+                for x in area.from.x..=area.to.x {
+                    pixels.blend(&Point::new(x, y), &style.fill);
+                }
+            }
+        }
+    }
+    if style.mode.has_outline() {
+        let points: Vec<Point> = rectangle
+            .edges()
+            .iter()
+            .flat_map(|edge| edge.points())
+            .collect();
+        let outline_bounds = style.brush.put_points(pixels, &points, &style.outline);
+        bounds = rectangle::union(bounds, outline_bounds);
+    }
+    bounds
+}
+
+/// Shift constrains the rectangle to a square, Alt draws it from the center outwards.
+fn get_rectangle(dom: &Dom, point_a: &Point, pointer_event: &PointerEvent) -> Rectangle {
+    let point_b = dom.canvas.get_point(pointer_event);
+    Rectangle::from_drag(
+        point_a,
+        &point_b,
+        pointer_event.shift_key(),
+        pointer_event.alt_key(),
+    )
+}
+
+fn read_style(dom: &Dom, colors: &Palette, brush: &Brush) -> Style {
+    let mode = shape::read_mode(dom, "rectangle-mode");
+    Style::new(
+        mode,
+        brush.clone(),
+        &colors.primary.borrow(),
+        &colors.secondary.borrow(),
+    )
+}
@@ -0,0 +1,154 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlElement, PointerEvent};
+
+use crate::canvas::selection::Selection;
+use crate::clipboard;
+use crate::dom::Dom;
+use crate::point::Point;
+use crate::rectangle::Rectangle;
+use crate::tool::tool::Tool;
+
+pub struct SelectTool {
+    drag: Option<SelectionDrag>,
+}
+
+impl SelectTool {
+    pub fn new() -> SelectTool {
+        SelectTool { drag: None }
+    }
+}
+
+impl Tool for SelectTool {
+    fn id(&self) -> &'static str {
+        "select"
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["select-options"]
+    }
+
+    fn on_press(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let point_a = dom.canvas.get_point(pointer_event);
+        if !dom.canvas.is_point_on_canvas(&point_a) {
+            return;
+        }
+        clipboard::commit_floating(dom);
+        self.drag = Some(SelectionDrag::begin(dom, point_a));
+    }
+
+    fn on_move(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        if let Some(drag) = &self.drag {
+            drag.advance(dom, pointer_event);
+        }
+    }
+
+    fn on_release(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        if let Some(drag) = self.drag.take() {
+            drag.end(dom, pointer_event);
+        }
+    }
+
+    fn on_cancel(&mut self, dom: &mut Dom) {
+        if let Some(drag) = self.drag.take() {
+            drag.cancel(dom);
+        }
+    }
+}
+
+/// Selection following the cursor from the point the drag started at.
+pub struct SelectionDrag {
+    point_a: Point,
+    /// Selection there was before the drag, put back if the drag is cancelled.
+    previous: Option<Selection>,
+}
+
+impl SelectionDrag {
+    pub fn begin(dom: &Dom, point_a: Point) -> SelectionDrag {
+        SelectionDrag {
+            point_a,
+            previous: dom.canvas.selection(),
+        }
+    }
+
+    pub fn advance(&self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let selection = get_selection(dom, &self.point_a, pointer_event);
+        dom.canvas.set_selection(selection);
+    }
+
+    /// A click without a drag drops the selection.
+    pub fn end(self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        let point_b = dom.canvas.get_point(pointer_event);
+        let selection = if point_b.x == self.point_a.x && point_b.y == self.point_a.y {
+            None
+        } else {
+            get_selection(dom, &self.point_a, pointer_event)
+        };
+        dom.canvas.set_selection(selection);
+    }
+
+    pub fn cancel(self, dom: &mut Dom) {
+        dom.canvas.set_selection(self.previous);
+    }
+}
+
+/// Binds the selection buttons, available whatever tool is selected.
+pub fn init_actions(dom: Rc<RefCell<Dom>>) {
+    bind_action(Rc::clone(&dom), "select-all", select_all);
+    bind_action(Rc::clone(&dom), "deselect", deselect);
+    bind_action(Rc::clone(&dom), "invert-selection", invert);
+}
+
+pub fn select_all(dom: &mut Dom) {
+    clipboard::commit_floating(dom);
+    let width = dom.canvas.element.width() as i32;
+    let height = dom.canvas.element.height() as i32;
+    let area = Rectangle::new(Point::new(0, 0), Point::new(width - 1, height - 1));
+    dom.canvas.set_selection(Some(Selection::new(area)));
+}
+
+pub fn deselect(dom: &mut Dom) {
+    clipboard::commit_floating(dom);
+    dom.canvas.set_selection(None);
+}
+
+/// Without a selection there is nothing to invert, so the whole canvas stays editable.
+pub fn invert(dom: &mut Dom) {
+    clipboard::commit_floating(dom);
+    let selection = dom.canvas.selection();
+    if let Some(selection) = selection {
+        dom.canvas.set_selection(Some(selection.inverted()));
+    }
+}
+
+/// Shift constrains the selection to a square, Alt draws it from the center outwards.
+fn get_selection(dom: &Dom, point_a: &Point, pointer_event: &PointerEvent) -> Option<Selection> {
+    let point_b = dom.canvas.get_point(pointer_event);
+    let area = Rectangle::from_drag(
+        point_a,
+        &point_b,
+        pointer_event.shift_key(),
+        pointer_event.alt_key(),
+    );
+    let width = dom.canvas.element.width();
+    let height = dom.canvas.element.height();
+    area.clip(&width, &height).map(Selection::new)
+}
+
+fn bind_action(dom: Rc<RefCell<Dom>>, id: &str, action: fn(&mut Dom)) {
+    let button = dom
+        .borrow()
+        .document
+        .get_element_by_id(id)
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap();
+    let on_click = Closure::<dyn FnMut()>::new(move || {
+        action(&mut dom.borrow_mut());
+    });
+    button.set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+}
@@ -0,0 +1,64 @@
+use wasm_bindgen::JsCast;
+use web_sys::HtmlSelectElement;
+
+use crate::brush::Brush;
+use crate::color::Color;
+use crate::dom::Dom;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    Outline,
+    Fill,
+    OutlineAndFill,
+}
+
+impl Mode {
+    pub fn has_outline(&self) -> bool {
+        *self != Mode::Fill
+    }
+
+    pub fn has_fill(&self) -> bool {
+        *self != Mode::Outline
+    }
+}
+
+/// How a shape is painted: its outline is stamped with the brush, its interior is filled.
+pub struct Style {
+    pub mode: Mode,
+    pub brush: Brush,
+    pub outline: Color,
+    pub fill: Color,
+}
+
+impl Style {
+    /// Shapes painted in a single color fill with the outline color, so `fill` only applies along
+    /// with an outline.
+    pub fn new(mode: Mode, brush: Brush, outline: &Color, fill: &Color) -> Style {
+        let fill = if mode == Mode::OutlineAndFill {
+            fill.clone()
+        } else {
+            outline.clone()
+        };
+        Style {
+            mode,
+            brush,
+            outline: outline.clone(),
+            fill,
+        }
+    }
+}
+
+pub fn read_mode(dom: &Dom, select_id: &str) -> Mode {
+    let mode = dom
+        .document
+        .get_element_by_id(select_id)
+        .unwrap()
+        .dyn_into::<HtmlSelectElement>()
+        .unwrap()
+        .value();
+    match mode.as_str() {
+        "fill" => Mode::Fill,
+        "outline-fill" => Mode::OutlineAndFill,
+        _ => Mode::Outline,
+    }
+}
@@ -0,0 +1,39 @@
+use web_sys::PointerEvent;
+
+use crate::dom::Dom;
+
+/// A tool of the tool bar. The manager passes it the pointer events of the canvas while it is
+/// active, so the tool only keeps the state of the drag in progress, if any.
+pub trait Tool {
+    /// Id of the tool bar element that selects the tool.
+    fn id(&self) -> &'static str;
+
+    /// Ids of the option panels shown while the tool is active.
+    fn options(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// CSS cursor shown over the canvas while the tool is active.
+    fn cursor(&self) -> &'static str {
+        "crosshair"
+    }
+
+    fn on_activate(&mut self, _dom: &mut Dom) {}
+
+    fn on_deactivate(&mut self, _dom: &mut Dom) {}
+
+    fn on_press(&mut self, _dom: &mut Dom, _pointer_event: &PointerEvent) {}
+
+    /// Called while the pointer hovers the viewport, and while it drags from there whether it
+    /// stays over the viewport or not.
+    fn on_move(&mut self, _dom: &mut Dom, _pointer_event: &PointerEvent) {}
+
+    /// Called when the pressed pointer is lifted, or when the viewport loses it.
+    fn on_release(&mut self, _dom: &mut Dom, _pointer_event: &PointerEvent) {}
+
+    /// Called when the pointer leaves the viewport without a button held.
+    fn on_leave(&mut self, _dom: &mut Dom) {}
+
+    /// Undoes the drag in progress, if any.
+    fn on_cancel(&mut self, _dom: &mut Dom) {}
+}
@@ -1,92 +1,163 @@
-use crate::color::Color;
+use crate::brush::{Brush, Tip};
 use crate::dom::Dom;
-use crate::tool::{color_picker, line, pencil};
+use crate::file;
+use crate::history::history;
+use crate::palette::Palette;
+use crate::tool::bucket::BucketTool;
+use crate::tool::ellipse::EllipseTool;
+use crate::tool::eyedropper::EyedropperTool;
+use crate::tool::line::LineTool;
+use crate::tool::rectangle::RectangleTool;
+use crate::tool::select::{self, SelectTool};
+use crate::tool::transform::{self, TransformTool};
+use crate::tool::{brush_options, color_picker, eraser, manager, pencil};
 use std::cell::RefCell;
 use std::rc::Rc;
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen::JsCast;
-use web_sys::{Document, HtmlElement};
+use web_sys::{Document, Element, HtmlElement, MouseEvent};
 
 pub struct ToolBar {
-    pub pencil: HtmlElement,
-    pub line: HtmlElement,
-    pub color: HtmlElement,
+    pub undo: HtmlElement,
+    pub redo: HtmlElement,
+    pub save: HtmlElement,
+    pub open: HtmlElement,
 }
 
 impl ToolBar {
     pub fn new(document: &Document) -> ToolBar {
-        let pencil = document
-            .get_element_by_id("pencil")
+        let undo = document
+            .get_element_by_id("undo")
             .unwrap()
             .dyn_into::<HtmlElement>()
             .unwrap();
-        let line = document
-            .get_element_by_id("line")
+        let redo = document
+            .get_element_by_id("redo")
             .unwrap()
             .dyn_into::<HtmlElement>()
             .unwrap();
-        let color = document
-            .get_element_by_id("color")
+        let save = document
+            .get_element_by_id("save")
+            .unwrap()
+            .dyn_into::<HtmlElement>()
+            .unwrap();
+        let open = document
+            .get_element_by_id("open")
             .unwrap()
             .dyn_into::<HtmlElement>()
             .unwrap();
         ToolBar {
-            pencil,
-            line,
-            color,
+            undo,
+            redo,
+            save,
+            open,
         }
     }
 }
 
 pub fn init(dom: Rc<RefCell<Dom>>) {
-    let color = Rc::new(RefCell::new(Color::black()));
+    let palette = dom.borrow().palette.clone();
+    let brush = Rc::new(RefCell::new(Brush::new(1, Tip::Round)));
+    brush_options::init(Rc::clone(&dom), Rc::clone(&brush));
+    select::init_actions(Rc::clone(&dom));
+    transform::init_options(Rc::clone(&dom));
+    {
+        let tools = &mut dom.borrow_mut().tools;
+        tools.register(SelectTool::new());
+        tools.register(TransformTool::new());
+        tools.register(pencil::new(palette.clone(), Rc::clone(&brush)));
+        tools.register(LineTool::new(palette.clone(), Rc::clone(&brush)));
+        tools.register(RectangleTool::new(palette.clone(), Rc::clone(&brush)));
+        tools.register(EllipseTool::new(palette.clone(), Rc::clone(&brush)));
+        tools.register(eraser::new());
+        tools.register(BucketTool::new(palette.clone()));
+        tools.register(EyedropperTool::new(palette.clone()));
+    }
     let tools = dom.borrow().document.get_elements_by_class_name("tool");
     for tool_idx in 0..tools.length() {
-        let tool = tools.item(tool_idx).unwrap();
-        match tool.id().as_str() {
-            "pencil" => {
-                let on_click = init_pencil(Rc::clone(&dom), Rc::clone(&color));
-                dom.borrow()
-                    .tool_bar
-                    .pencil
-                    .set_onclick(Some(on_click.as_ref().unchecked_ref()));
-                on_click.forget();
-            }
-            "line" => {
-                let on_click = init_line(Rc::clone(&dom), Rc::clone(&color));
-                dom.borrow()
-                    .tool_bar
-                    .line
-                    .set_onclick(Some(on_click.as_ref().unchecked_ref()));
-                on_click.forget();
-            }
-            "color" => {
-                let on_click = init_color_picker(Rc::clone(&dom), Rc::clone(&color));
-                dom.borrow()
-                    .tool_bar
-                    .color
-                    .set_onclick(Some(on_click.as_ref().unchecked_ref()));
-                on_click.forget();
-            }
-            _ => panic!("Unsupported tool"),
+        let tool = tools
+            .item(tool_idx)
+            .unwrap()
+            .dyn_into::<HtmlElement>()
+            .unwrap();
+        // The color tool only opens its dialog, leaving the active tool as it is.
+        if tool.id() == "color" {
+            let on_click = init_color_picker(Rc::clone(&dom), palette.clone());
+            tool.set_onclick(Some(on_click.as_ref().unchecked_ref()));
+            on_click.forget();
+            continue;
         }
+        let on_click = select(Rc::clone(&dom), tool.id());
+        tool.set_onclick(Some(on_click.as_ref().unchecked_ref()));
+        on_click.forget();
     }
+    let on_click = undo(Rc::clone(&dom));
+    dom.borrow()
+        .tool_bar
+        .undo
+        .set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    let on_click = redo(Rc::clone(&dom));
+    dom.borrow()
+        .tool_bar
+        .redo
+        .set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    let on_click = save(Rc::clone(&dom));
+    dom.borrow()
+        .tool_bar
+        .save
+        .set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    let on_click = open(Rc::clone(&dom));
+    dom.borrow()
+        .tool_bar
+        .open
+        .set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+}
+
+fn select(dom: Rc<RefCell<Dom>>, id: String) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        manager::select(&mut dom.borrow_mut(), &id);
+    })
+}
+
+/// Edits the secondary color when its swatch is clicked, and the primary one otherwise.
+fn init_color_picker(dom: Rc<RefCell<Dom>>, palette: Palette) -> Closure<dyn FnMut(MouseEvent)> {
+    Closure::wrap(Box::new(move |mouse_event: MouseEvent| {
+        let is_secondary = mouse_event
+            .target()
+            .and_then(|target| target.dyn_into::<Element>().ok())
+            .is_some_and(|target| target.id() == "secondary-color");
+        let color = match is_secondary {
+            true => Rc::clone(&palette.secondary),
+            false => Rc::clone(&palette.primary),
+        };
+        color_picker::init(Rc::clone(&dom), color);
+    }) as Box<dyn FnMut(MouseEvent)>)
+}
+
+fn undo(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut()> {
+    Closure::<dyn FnMut()>::new(move || {
+        history::undo(&dom);
+    })
 }
 
-fn init_pencil(dom: Rc<RefCell<Dom>>, color: Rc<RefCell<Color>>) -> Closure<dyn FnMut()> {
+fn redo(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut()> {
     Closure::<dyn FnMut()>::new(move || {
-        pencil::init(Rc::clone(&dom), Rc::clone(&color));
+        history::redo(&dom);
     })
 }
 
-fn init_line(dom: Rc<RefCell<Dom>>, color: Rc<RefCell<Color>>) -> Closure<dyn FnMut()> {
+fn save(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut()> {
     Closure::<dyn FnMut()>::new(move || {
-        line::init(Rc::clone(&dom), Rc::clone(&color));
+        file::save(&dom.borrow());
     })
 }
 
-fn init_color_picker(dom: Rc<RefCell<Dom>>, color: Rc<RefCell<Color>>) -> Closure<dyn FnMut()> {
+fn open(dom: Rc<RefCell<Dom>>) -> Closure<dyn FnMut()> {
     Closure::<dyn FnMut()>::new(move || {
-        color_picker::init(Rc::clone(&dom), Rc::clone(&color));
+        file::open(&dom.borrow());
     })
 }
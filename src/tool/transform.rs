@@ -0,0 +1,302 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlElement, HtmlInputElement, HtmlSelectElement, PointerEvent};
+
+use crate::canvas::overlay::HANDLE_SIZE;
+use crate::canvas::transform::{Handle, Resampling, Transform};
+use crate::clipboard;
+use crate::dom::Dom;
+use crate::point::Point;
+use crate::rectangle::Rectangle;
+use crate::tool::select::SelectionDrag;
+use crate::tool::tool::Tool;
+
+pub struct TransformTool {
+    drag: Option<Drag>,
+    /// Transform and center of the floating pixels when they were grabbed, put back if the drag
+    /// is cancelled.
+    grabbed: Option<(Transform, (f64, f64))>,
+}
+
+enum Drag {
+    /// Selects the area to lift.
+    Selecting(SelectionDrag),
+    /// Moves the floating pixels grabbed at the offset from their top left corner.
+    Shifting(Point),
+    /// Scales the floating pixels by the handle, with the area and transform they had when it
+    /// was grabbed.
+    Scaling(Handle, Rectangle, Transform),
+}
+
+impl TransformTool {
+    pub fn new() -> TransformTool {
+        TransformTool {
+            drag: None,
+            grabbed: None,
+        }
+    }
+
+    /// Follows the cursor with the floating pixels until the button is released.
+    fn grab(&mut self, dom: &Dom, drag: Drag) {
+        self.grabbed = dom
+            .canvas
+            .floating()
+            .map(|floating| (floating.transform(), floating.center()));
+        self.drag = Some(drag);
+    }
+}
+
+impl Tool for TransformTool {
+    fn id(&self) -> &'static str {
+        "transform"
+    }
+
+    fn options(&self) -> &'static [&'static str] {
+        &["transform-options"]
+    }
+
+    fn cursor(&self) -> &'static str {
+        "move"
+    }
+
+    fn on_activate(&mut self, dom: &mut Dom) {
+        show_angle(dom);
+    }
+
+    /// Pastes the floating pixels before another tool gets to the canvas.
+    fn on_deactivate(&mut self, dom: &mut Dom) {
+        clipboard::commit_floating(dom);
+    }
+
+    /// Grabs a handle or the body of the floating pixels. Anywhere else, the selected pixels are
+    /// lifted when grabbed, and a new area is selected otherwise.
+    fn on_press(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        if let Some((handle, area, transform)) = find_handle(dom, pointer_event) {
+            self.grab(dom, Drag::Scaling(handle, area, transform));
+            return;
+        }
+        let point = dom.canvas.get_point(pointer_event);
+        let is_on_floating = dom
+            .canvas
+            .floating()
+            .is_some_and(|floating| floating.area().contains(&point));
+        if !is_on_floating {
+            if !dom.canvas.is_point_on_canvas(&point) {
+                return;
+            }
+            clipboard::commit_floating(dom);
+            let is_on_selection = dom
+                .canvas
+                .selection()
+                .is_some_and(|selection| !selection.is_inverted && selection.area.contains(&point));
+            if !is_on_selection {
+                self.drag = Some(Drag::Selecting(SelectionDrag::begin(dom, point)));
+                return;
+            }
+            lift(dom);
+        }
+        let at = match dom.canvas.floating() {
+            Some(floating) => floating.at,
+            None => return,
+        };
+        let offset = Point::new(point.x - at.x, point.y - at.y);
+        self.grab(dom, Drag::Shifting(offset));
+    }
+
+    fn on_move(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        match &self.drag {
+            Some(Drag::Selecting(selecting)) => selecting.advance(dom, pointer_event),
+            Some(Drag::Shifting(offset)) => shift(dom, offset, pointer_event),
+            Some(Drag::Scaling(handle, area, transform)) => {
+                scale(dom, *handle, area, transform, pointer_event)
+            }
+            None => {}
+        }
+    }
+
+    /// Lifts what has just been selected.
+    fn on_release(&mut self, dom: &mut Dom, pointer_event: &PointerEvent) {
+        if let Some(Drag::Selecting(selecting)) = self.drag.take() {
+            selecting.end(dom, pointer_event);
+            lift(dom);
+        }
+    }
+
+    /// Puts the floating pixels back where, and as, they were grabbed.
+    fn on_cancel(&mut self, dom: &mut Dom) {
+        match self.drag.take() {
+            Some(Drag::Selecting(selecting)) => selecting.cancel(dom),
+            Some(_) => {
+                if let Some((transform, center)) = self.grabbed {
+                    if let Some(mut floating) = dom.canvas.take_floating() {
+                        floating.set_transform(transform, center);
+                        dom.canvas.set_floating(floating);
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+/// Binds the options that transform the floating pixels as soon as they change.
+pub fn init_options(dom: Rc<RefCell<Dom>>) {
+    let on_change = Closure::<dyn FnMut()>::new({
+        let dom = Rc::clone(&dom);
+        move || {
+            let dom = &mut *dom.borrow_mut();
+            let resampling = read_resampling(dom);
+            let angle = read_angle(dom);
+            update(dom, |transform| {
+                transform.resampling = resampling;
+                transform.angle = angle;
+            });
+        }
+    });
+    for id in ["transform-resampling", "transform-angle"] {
+        dom.borrow()
+            .document
+            .get_element_by_id(id)
+            .unwrap()
+            .add_event_listener_with_callback("input", on_change.as_ref().unchecked_ref())
+            .unwrap();
+    }
+    on_change.forget();
+    let on_flip = Closure::<dyn FnMut()>::new({
+        let dom = Rc::clone(&dom);
+        move || {
+            update(&mut dom.borrow_mut(), |transform| {
+                transform.is_flipped_horizontally = !transform.is_flipped_horizontally;
+            })
+        }
+    });
+    get_button(&dom.borrow(), "flip-horizontal")
+        .set_onclick(Some(on_flip.as_ref().unchecked_ref()));
+    on_flip.forget();
+    let on_flip = Closure::<dyn FnMut()>::new({
+        let dom = Rc::clone(&dom);
+        move || {
+            update(&mut dom.borrow_mut(), |transform| {
+                transform.is_flipped_vertically = !transform.is_flipped_vertically;
+            })
+        }
+    });
+    get_button(&dom.borrow(), "flip-vertical").set_onclick(Some(on_flip.as_ref().unchecked_ref()));
+    on_flip.forget();
+}
+
+/// Changes the transform of the floating pixels, keeping them centered where they are.
+fn update(dom: &mut Dom, change: impl FnOnce(&mut Transform)) {
+    if let Some(mut floating) = dom.canvas.take_floating() {
+        let mut transform = floating.transform();
+        change(&mut transform);
+        let center = floating.center();
+        floating.set_transform(transform, center);
+        dom.canvas.set_floating(floating);
+    }
+}
+
+fn lift(dom: &mut Dom) {
+    clipboard::lift(dom);
+    show_angle(dom);
+}
+
+/// Moves the floating pixels, keeping the point they were grabbed at under the cursor.
+fn shift(dom: &mut Dom, offset: &Point, pointer_event: &PointerEvent) {
+    let point = dom.canvas.get_point(pointer_event);
+    if let Some(mut floating) = dom.canvas.take_floating() {
+        floating.at = Point::new(point.x - offset.x, point.y - offset.y);
+        dom.canvas.set_floating(floating);
+    }
+}
+
+/// Scales the floating pixels by as much as the handle stretches their area, which was `area`
+/// when the handle was grabbed with the pixels transformed by `transform`.
+fn scale(
+    dom: &mut Dom,
+    handle: Handle,
+    area: &Rectangle,
+    transform: &Transform,
+    pointer_event: &PointerEvent,
+) {
+    let position = dom.canvas.get_position(pointer_event);
+    let target = handle.drag(area, position);
+    let width = transform.width as f64 * target.width() as f64 / area.width() as f64;
+    let height = transform.height as f64 * target.height() as f64 / area.height() as f64;
+    let transform = Transform {
+        width: width.round().max(1.0) as u32,
+        height: height.round().max(1.0) as u32,
+        resampling: read_resampling(dom),
+        ..*transform
+    };
+    let center = (
+        (target.from.x + target.to.x + 1) as f64 / 2.0,
+        (target.from.y + target.to.y + 1) as f64 / 2.0,
+    );
+    if let Some(mut floating) = dom.canvas.take_floating() {
+        floating.set_transform(transform, center);
+        dom.canvas.set_floating(floating);
+    }
+}
+
+/// Handle of the floating pixels under the cursor, if any, along with their area and transform.
+fn find_handle(dom: &Dom, pointer_event: &PointerEvent) -> Option<(Handle, Rectangle, Transform)> {
+    let floating = dom.canvas.floating()?;
+    let area = floating.area();
+    let (x, y) = dom.canvas.get_position(pointer_event);
+    let reach = HANDLE_SIZE / 2.0 / dom.canvas.viewport.zoom;
+    let handle = Handle::ALL.iter().copied().find(|handle| {
+        let (handle_x, handle_y) = handle.position(&area);
+        (x - handle_x).abs() <= reach && (y - handle_y).abs() <= reach
+    })?;
+    Some((handle, area, floating.transform()))
+}
+
+/// Shows the angle of the floating pixels, or zero when nothing floats.
+pub fn show_angle(dom: &Dom) {
+    let angle = dom
+        .canvas
+        .floating()
+        .map_or(0.0, |floating| floating.transform().angle);
+    get_input(dom, "transform-angle").set_value(&angle.to_string());
+}
+
+fn read_angle(dom: &Dom) -> f64 {
+    get_input(dom, "transform-angle")
+        .value()
+        .parse::<f64>()
+        .unwrap_or(0.0)
+}
+
+fn read_resampling(dom: &Dom) -> Resampling {
+    let value = dom
+        .document
+        .get_element_by_id("transform-resampling")
+        .unwrap()
+        .dyn_into::<HtmlSelectElement>()
+        .unwrap()
+        .value();
+    match value.as_str() {
+        "bilinear" => Resampling::Bilinear,
+        _ => Resampling::Nearest,
+    }
+}
+
+fn get_input(dom: &Dom, id: &str) -> HtmlInputElement {
+    dom.document
+        .get_element_by_id(id)
+        .unwrap()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+}
+
+fn get_button(dom: &Dom, id: &str) -> HtmlElement {
+    dom.document
+        .get_element_by_id(id)
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
+}